@@ -0,0 +1,56 @@
+//! Plays the same seeded games with the built-in strategies on a [`Game`] and on a [`BitGame`],
+//! checking they end the same and printing how long each took. Run with `cargo bench`.
+
+use std::time::{Duration, Instant};
+
+use twozero48::{Game, Tile, bitboard::BitGame, spawn::SpawnRules, strategy};
+
+/// Strategies compared, with the number of games each plays
+const STRATEGIES: [(&str, u64); 4] = [
+    ("expectimax:2", 20),
+    ("greedy", 2000),
+    ("corner", 2000),
+    ("random", 2000),
+];
+
+/// Plays every game, returns the scores and how long playing them took
+fn time(games: u64, mut play: impl FnMut(u64) -> usize) -> (Vec<usize>, Duration) {
+    let started = Instant::now();
+    let scores = (0..games).map(&mut play).collect();
+
+    (scores, started.elapsed())
+}
+
+fn main() {
+    let rules = SpawnRules::default();
+    let winning = Tile::TWO_THOUSAND_FORTY_EIGHT;
+    println!(
+        "{:<14}  {:>6}  {:>10}  {:>10}  {:>7}",
+        "Strategy", "Games", "Game", "BitGame", "Speedup"
+    );
+    for (name, games) in STRATEGIES {
+        let strategy = |seed| strategy::builtin(name, seed, &rules).expect("strategy is built in");
+        let (scores, slow) = time(games, |seed| {
+            let mut game = Game::with_rules(4, winning, rules.clone(), seed);
+            strategy::play_out(&mut game, strategy(seed).as_mut());
+            game.score()
+        });
+        let (fast_scores, fast) = time(games, |seed| {
+            let mut game = BitGame::with_rules(winning, rules.clone(), seed).expect("rules fit");
+            let overflow = strategy::play_out_bits(&mut game, strategy(seed).as_mut());
+            assert!(overflow.is_none(), "games don't reach two 32768s");
+            game.score()
+        });
+        assert_eq!(
+            scores, fast_scores,
+            "{name} played differently on a bitboard"
+        );
+
+        println!(
+            "{name:<14}  {games:>6}  {:>10.2?}  {:>10.2?}  {:>6.1}x",
+            slow,
+            fast,
+            slow.as_secs_f64() / fast.as_secs_f64()
+        );
+    }
+}
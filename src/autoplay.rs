@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant};
+
+const MIN_SPEED: Duration = Duration::from_millis(10);
+const MAX_SPEED: Duration = Duration::from_millis(5000);
+
+/// Paces the moves played by the solver while it is in control of the game.
+/// Speed is the time between two moves, so faster is a shorter duration.
+pub struct Autoplay {
+    speed: Duration,
+    paused: bool,
+    next_move: Instant,
+}
+
+impl Autoplay {
+    pub fn new(speed: Duration) -> Self {
+        let speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+        Self {
+            speed,
+            paused: false,
+            next_move: Instant::now() + speed,
+        }
+    }
+
+    pub fn speed(&self) -> Duration {
+        self.speed
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.next_move = Instant::now() + self.speed;
+    }
+
+    /// Halves the time between moves
+    pub fn faster(&mut self) {
+        self.speed = (self.speed / 2).max(MIN_SPEED);
+    }
+
+    /// Doubles the time between moves
+    pub fn slower(&mut self) {
+        self.speed = (self.speed * 2).min(MAX_SPEED);
+    }
+
+    /// Time left until the next move is due, `None` while paused
+    pub fn wait(&self) -> Option<Duration> {
+        (!self.paused).then(|| self.next_move.saturating_duration_since(Instant::now()))
+    }
+
+    pub fn is_due(&self) -> bool {
+        !self.paused && Instant::now() >= self.next_move
+    }
+
+    /// Schedules the next move, one step after this one
+    pub fn played(&mut self) {
+        self.next_move = Instant::now() + self.speed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_stays_within_bounds() {
+        let mut autoplay = Autoplay::new(Duration::from_millis(40));
+        for _ in 0..10 {
+            autoplay.faster();
+        }
+        assert_eq!(autoplay.speed(), MIN_SPEED);
+        for _ in 0..20 {
+            autoplay.slower();
+        }
+        assert_eq!(autoplay.speed(), MAX_SPEED);
+    }
+
+    #[test]
+    fn paused_autoplay_never_moves() {
+        let mut autoplay = Autoplay::new(Duration::ZERO);
+        autoplay.toggle_pause();
+        assert!(autoplay.wait().is_none());
+        assert!(!autoplay.is_due());
+    }
+}
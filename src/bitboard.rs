@@ -0,0 +1,645 @@
+//! Packed 4x4 boards for when games have to be played fast, e.g. by searches and simulations.
+//! A [`Bitboard`] keeps the exponent of every tile in 4 bits of a `u64`, and moves a whole
+//! row at once by looking it up in tables built from the same rules as [`slide`](crate::slide).
+//!
+//! Nibbles fit exponents up to 15, so tiles go up to 32768 and two 32768s can't merge on a
+//! bitboard. [`Bitboard::overflows`] tells when they would, [`BitGame::mover`] refuses such
+//! moves so the game can be handed back to a [`Game`] with [`BitGame::to_game`].
+//! Blocked cells aren't supported, [`BitGame`] plays standard games with random spawns.
+
+use std::{error::Error, fmt::Display, sync::OnceLock};
+
+use rand::{Rng, RngExt, SeedableRng};
+
+use crate::{
+    Board, Cell, Game, GameRng, LossReason, Move, Status, Tile, compress, spawn::SpawnRules,
+};
+
+/// Number of cells along each side of the board
+pub const SIDE: usize = 4;
+/// Largest exponent a nibble holds, 32768 = 2^15
+const MAX_EXPONENT: u8 = 15;
+const ROW_MASK: u64 = 0xFFFF;
+
+/// Rows after moving towards their start or end, the score each move earns and whether it
+/// would merge two 32768s, indexed by the row as 16 bits with the first cell in the lowest nibble
+struct Tables {
+    left: Vec<u16>,
+    right: Vec<u16>,
+    left_score: Vec<u32>,
+    right_score: Vec<u32>,
+    left_overflow: Vec<bool>,
+    right_overflow: Vec<bool>,
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let rows = 1 << 16;
+        let mut tables = Tables {
+            left: Vec::with_capacity(rows),
+            right: Vec::with_capacity(rows),
+            left_score: Vec::with_capacity(rows),
+            right_score: Vec::with_capacity(rows),
+            left_overflow: Vec::with_capacity(rows),
+            right_overflow: Vec::with_capacity(rows),
+        };
+        for row in 0..rows {
+            let cells: [Tile; SIDE] = std::array::from_fn(|c| Tile((row >> (4 * c)) as u8 & 0xF));
+            let (left, left_score, left_overflow) = move_row(cells);
+            let mut reversed = cells;
+            reversed.reverse();
+            let (right, right_score, right_overflow) = move_row(reversed);
+            tables.left.push(left);
+            tables.left_score.push(left_score);
+            tables.left_overflow.push(left_overflow);
+            tables.right.push(reverse_row(right));
+            tables.right_score.push(right_score);
+            tables.right_overflow.push(right_overflow);
+        }
+
+        tables
+    })
+}
+
+/// Compresses the row towards its first cell, with the score it earns and whether two 32768s
+/// met
+fn move_row(mut cells: [Tile; SIDE]) -> (u16, u32, bool) {
+    let before = cells;
+    let mut score = compress(&mut cells, |_, _, _| {});
+    let overflow = cells.iter().any(|tile| tile.exponent() > MAX_EXPONENT);
+    if overflow {
+        // Two 32768s met, they slide like any tiles but stay apart
+        cells = [Tile::EMPTY; SIDE];
+        score = 0;
+        let mut end = 0;
+        let mut mergeable = false;
+        for tile in before.into_iter().filter(|&tile| tile != Tile::EMPTY) {
+            if mergeable && cells[end - 1] == tile && tile.exponent() < MAX_EXPONENT {
+                cells[end - 1] = tile.promote();
+                score += cells[end - 1].score();
+                mergeable = false;
+            } else {
+                cells[end] = tile;
+                end += 1;
+                mergeable = true;
+            }
+        }
+    }
+    let row = cells.iter().enumerate().fold(0, |row, (c, tile)| {
+        row | (tile.exponent() as u16) << (4 * c)
+    });
+
+    (row, score as u32, overflow)
+}
+
+/// Swaps the order of the cells of a row
+fn reverse_row(row: u16) -> u16 {
+    (row >> 12) | ((row >> 4) & 0x00F0) | ((row << 4) & 0x0F00) | (row << 12)
+}
+
+/// A 4x4 board packed in a `u64`, the cell at row `r` and column `c` is the nibble `4r + c`
+/// counting from the lowest, which holds the tile's exponent
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Bitboard(pub u64);
+
+impl Bitboard {
+    /// Packs the board, `None` unless it is 4x4 without blocked cells or tiles above 32768
+    pub fn from_board(board: &Board) -> Option<Self> {
+        if board.len() != SIDE || board.iter().any(|row| row.len() != SIDE) {
+            return None;
+        }
+        let mut packed = Bitboard(0);
+        for (r, row) in board.iter().enumerate() {
+            for (c, &tile) in row.iter().enumerate() {
+                if tile.exponent() > MAX_EXPONENT {
+                    return None;
+                }
+                packed.set((r, c), tile);
+            }
+        }
+
+        Some(packed)
+    }
+
+    pub fn to_board(self) -> Board {
+        (0..SIDE)
+            .map(|r| (0..SIDE).map(|c| self.get((r, c))).collect())
+            .collect()
+    }
+
+    pub fn get(self, (r, c): Cell) -> Tile {
+        Tile((self.0 >> (4 * (SIDE * r + c))) as u8 & 0xF)
+    }
+
+    /// Puts the tile in the cell, it must fit in a nibble
+    pub fn set(&mut self, (r, c): Cell, tile: Tile) {
+        debug_assert!(tile.exponent() <= MAX_EXPONENT);
+        let shift = 4 * (SIDE * r + c);
+        self.0 = (self.0 & !(0xF << shift)) | (tile.exponent() as u64) << shift;
+    }
+
+    /// Empty cells in reading order, like the [`RandomSpawner`](crate::spawn::RandomSpawner)
+    /// sees them
+    pub fn empty_cells(self) -> impl Iterator<Item = Cell> {
+        (0..SIDE * SIDE)
+            .filter(move |i| (self.0 >> (4 * i)) & 0xF == 0)
+            .map(|i| (i / SIDE, i % SIDE))
+    }
+
+    pub fn largest_tile(self) -> Tile {
+        (0..SIDE * SIDE)
+            .map(|i| Tile((self.0 >> (4 * i)) as u8 & 0xF))
+            .max()
+            .unwrap_or(Tile::EMPTY)
+    }
+
+    /// Rows as 16 bits each, with the first cell in the lowest nibble
+    pub fn rows(self) -> [u16; SIDE] {
+        std::array::from_fn(|r| ((self.0 >> (16 * r)) & ROW_MASK) as u16)
+    }
+
+    /// Mirrors the board along its main diagonal, rows become columns
+    pub fn transpose(self) -> Self {
+        let x = self.0;
+        let a = (x & 0xF0F0_0F0F_F0F0_0F0F)
+            | ((x & 0x0000_F0F0_0000_F0F0) << 12)
+            | ((x & 0x0F0F_0000_0F0F_0000) >> 12);
+        let b = (a & 0xFF00_FF00_00FF_00FF)
+            | ((a & 0x00FF_00FF_0000_0000) >> 24)
+            | ((a & 0x0000_0000_FF00_FF00) << 24);
+
+        Bitboard(b)
+    }
+
+    /// Moves every row through the table, with the score of the merges
+    fn slide_rows(self, table: &[u16], score: &[u32]) -> (Self, usize) {
+        let mut moved = 0;
+        let mut earned = 0;
+        for r in 0..SIDE {
+            let row = ((self.0 >> (16 * r)) & ROW_MASK) as usize;
+            moved |= (table[row] as u64) << (16 * r);
+            earned += score[row] as usize;
+        }
+
+        (Bitboard(moved), earned)
+    }
+
+    /// Whether any row would merge in the table
+    fn rows_overflow(self, overflow: &[bool]) -> bool {
+        (0..SIDE).any(|r| overflow[((self.0 >> (16 * r)) & ROW_MASK) as usize])
+    }
+
+    /// Whether the move would merge two 32768s, which [`slide`](Self::slide) can't do
+    pub fn overflows(self, mov: Move) -> bool {
+        let tables = tables();
+        match mov {
+            Move::Left => self.rows_overflow(&tables.left_overflow),
+            Move::Right => self.rows_overflow(&tables.right_overflow),
+            Move::Up => self.transpose().rows_overflow(&tables.left_overflow),
+            Move::Down => self.transpose().rows_overflow(&tables.right_overflow),
+            Move::Dont => false,
+        }
+    }
+
+    /// The board after the move along with the score it earns, like [`slide`](crate::slide)
+    /// except that two 32768s stay apart, see [`overflows`](Self::overflows)
+    pub fn slide(self, mov: Move) -> (Self, usize) {
+        let tables = tables();
+        match mov {
+            Move::Left => self.slide_rows(&tables.left, &tables.left_score),
+            Move::Right => self.slide_rows(&tables.right, &tables.right_score),
+            Move::Up => {
+                let (moved, score) = self
+                    .transpose()
+                    .slide_rows(&tables.left, &tables.left_score);
+                (moved.transpose(), score)
+            }
+            Move::Down => {
+                let (moved, score) = self
+                    .transpose()
+                    .slide_rows(&tables.right, &tables.right_score);
+                (moved.transpose(), score)
+            }
+            Move::Dont => (self, 0),
+        }
+    }
+
+    /// Returns the moves that change the board, including those that merge two 32768s
+    pub fn legal_moves(self) -> impl Iterator<Item = Move> {
+        Move::ALL
+            .into_iter()
+            .filter(move |&mov| self.slide(mov).0 != self || self.overflows(mov))
+    }
+
+    /// Whether the board is full and no tiles can merge
+    pub fn is_locked(self) -> bool {
+        self.empty_cells().next().is_none()
+            && self.slide(Move::Left).0 == self
+            && self.slide(Move::Up).0 == self
+            && !self.overflows(Move::Left)
+            && !self.overflows(Move::Up)
+    }
+}
+
+/// Whether every tile the rules spawn fits a nibble
+fn fits(rules: &SpawnRules) -> bool {
+    rules
+        .weights()
+        .iter()
+        .all(|(tile, _)| tile.exponent() <= MAX_EXPONENT)
+}
+
+/// A move [`BitGame::mover`] can't make, it would merge two 32768s into a tile that doesn't
+/// fit a nibble
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Overflow;
+
+impl Display for Overflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the move merges two 32768s, which a bitboard can't hold")
+    }
+}
+
+impl Error for Overflow {}
+
+/// A [`Game`] on a [`Bitboard`], with the same rules, spawns and scores for the same seed and
+/// moves. Moves can't be undone, there is no history to keep.
+#[derive(Clone, Debug)]
+pub struct BitGame<R = GameRng> {
+    board: Bitboard,
+    winning: Tile,
+    continued: bool,
+    rules: SpawnRules,
+    score: usize,
+    moves: usize,
+    rng: R,
+    seed: Option<u64>,
+    move_limit: Option<usize>,
+}
+
+impl BitGame {
+    /// Constructs the game [`Game::with_seed`] would on a 4x4 board
+    pub fn with_seed(winning: Tile, seed: u64) -> Self {
+        Self::with_rules(winning, SpawnRules::default(), seed)
+            .expect("standard tiles fit a bitboard")
+    }
+
+    /// Constructs the game [`Game::with_rules`] would on a 4x4 board, `None` if the rules
+    /// spawn tiles above 32768
+    pub fn with_rules(winning: Tile, rules: SpawnRules, seed: u64) -> Option<Self> {
+        if !fits(&rules) {
+            return None;
+        }
+        let mut game = Self {
+            board: Bitboard(0),
+            winning,
+            continued: false,
+            rules,
+            score: 0,
+            moves: 0,
+            rng: GameRng::seed_from_u64(seed),
+            seed: Some(seed),
+            move_limit: None,
+        };
+        for _ in 0..game.rules.start_tiles() {
+            game.spawn();
+        }
+
+        Some(game)
+    }
+}
+
+impl<R: Rng + Clone> BitGame<R> {
+    /// Takes over a game where it is, `None` unless it is a 4x4 game without blocked cells
+    /// or a goal, whose tiles spawn at random and fit a [`Bitboard`]
+    pub fn from_game(game: &Game<R>) -> Option<Self> {
+        if game.spawner().name() != "random" || game.goal().is_some() || !fits(game.spawn_rules()) {
+            return None;
+        }
+
+        Some(Self {
+            board: Bitboard::from_board(game.board())?,
+            winning: game.winning(),
+            continued: game.continued,
+            rules: game.spawn_rules().clone(),
+            score: game.score(),
+            moves: game.moves(),
+            rng: game.rng.clone(),
+            seed: game.seed(),
+            move_limit: game.move_limit(),
+        })
+    }
+
+    /// Hands the game back to a [`Game`] where it is, to go on with moves a bitboard can't
+    /// make. The same seed and moves spawn the same tiles in either
+    pub fn to_game(&self) -> Game<R> {
+        let mut game = Game::empty(
+            SIDE.into(),
+            self.winning,
+            self.rules.clone(),
+            self.rng.clone(),
+        );
+        game.board = self.board.to_board();
+        game.continued = self.continued;
+        game.score = self.score;
+        game.moves = self.moves;
+        game.seed = self.seed;
+        game.move_limit = self.move_limit;
+
+        game
+    }
+
+    pub fn board(&self) -> Bitboard {
+        self.board
+    }
+
+    pub fn score(&self) -> usize {
+        self.score
+    }
+
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
+
+    /// Same as [`Game::limit_moves`]
+    pub fn limit_moves(&mut self, limit: usize) {
+        self.move_limit = Some(limit);
+    }
+
+    pub fn moves_left(&self) -> Option<usize> {
+        self.move_limit
+            .map(|limit| limit.saturating_sub(self.moves))
+    }
+
+    pub fn has_won(&self) -> bool {
+        self.board.largest_tile() >= self.winning
+    }
+
+    pub fn keep_playing(&mut self) {
+        self.continued = true;
+    }
+
+    /// Same as [`Game::status`]
+    pub fn status(&self) -> Status {
+        let won = self.has_won();
+        if won && !self.continued {
+            Status::Won
+        } else if self.moves_left() == Some(0) {
+            Status::Lost(LossReason::OutOfMoves)
+        } else if self.board.is_locked() {
+            Status::Lost(LossReason::Locked)
+        } else if won {
+            Status::WonContinuing
+        } else {
+            Status::On
+        }
+    }
+
+    /// Same as [`Game::mover`], false if the move doesn't change the board. Moves that merge
+    /// two 32768s are refused with [`Overflow`] and leave the game as it was, [`to_game`]
+    /// takes it on from there
+    ///
+    /// [`to_game`]: Self::to_game
+    pub fn mover(&mut self, mov: Move) -> Result<bool, Overflow> {
+        if self.moves_left() == Some(0) {
+            return Ok(false);
+        }
+        if self.board.overflows(mov) {
+            return Err(Overflow);
+        }
+        let (moved, score) = self.board.slide(mov);
+        if moved == self.board {
+            return Ok(false);
+        }
+        self.board = moved;
+        self.score += score;
+        self.moves += 1;
+
+        Ok(true)
+    }
+
+    /// Same as [`Game::refresh`], spawns the tiles that follow a move
+    pub fn refresh(&mut self) {
+        for _ in 0..self.rules.count() {
+            if !self.spawn() {
+                break;
+            }
+        }
+    }
+
+    /// Spawns a tile on a random empty cell, drawing from the generator in the same order
+    /// as the [`RandomSpawner`](crate::spawn::RandomSpawner). False if the board is full
+    fn spawn(&mut self) -> bool {
+        let empty = self.board.empty_cells().count();
+        if empty == 0 {
+            return false;
+        }
+        let index = self.rng.random_range(0..empty);
+        let at = self
+            .board
+            .empty_cells()
+            .nth(index)
+            .expect("index is below the number of empty cells");
+        let tile = self.rules.pick(&mut self.rng);
+        self.board.set(at, tile);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{legal_moves, slide};
+
+    fn board(rows: [[usize; SIDE]; SIDE]) -> Board {
+        rows.iter()
+            .map(|row| row.iter().map(|&v| Tile::from_score(v).unwrap()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn boards_pack_and_unpack() {
+        let rows = board([
+            [2, 0, 4, 8],
+            [0, 16, 0, 0],
+            [32768, 2, 2, 0],
+            [0, 0, 0, 1024],
+        ]);
+        let packed = Bitboard::from_board(&rows).unwrap();
+        assert_eq!(packed.to_board(), rows);
+        assert_eq!(packed.get((2, 0)), Tile(15));
+        assert_eq!(packed.largest_tile(), Tile(15));
+        assert_eq!(packed.empty_cells().count(), 8);
+        assert_eq!(packed.empty_cells().next(), Some((0, 1)));
+
+        let transposed = packed.transpose();
+        for r in 0..SIDE {
+            for c in 0..SIDE {
+                assert_eq!(transposed.get((c, r)), packed.get((r, c)));
+            }
+        }
+        assert_eq!(transposed.transpose(), packed);
+
+        assert!(Bitboard::from_board(&vec![vec![Tile::TWO; 3]; 3]).is_none());
+        let mut blocked = rows.clone();
+        blocked[0][0] = Tile::BLOCKED;
+        assert!(Bitboard::from_board(&blocked).is_none());
+        assert_eq!(reverse_row(0x4321), 0x1234);
+    }
+
+    #[test]
+    fn slides_match_the_board_rules() {
+        let mut rng = GameRng::seed_from_u64(11);
+        for _ in 0..2000 {
+            // Mostly empty cells and small tiles, so rows merge often
+            let rows: Board = (0..SIDE)
+                .map(|_| {
+                    (0..SIDE)
+                        .map(|_| Tile(rng.random_range(0..10u8).saturating_sub(4)))
+                        .collect()
+                })
+                .collect();
+            let packed = Bitboard::from_board(&rows).unwrap();
+            assert_eq!(
+                packed.legal_moves().collect::<Vec<_>>(),
+                legal_moves(&rows).collect::<Vec<_>>()
+            );
+            for mov in Move::ALL {
+                let mut moved = rows.clone();
+                let score = slide(&mut moved, mov);
+                assert_eq!(
+                    packed.slide(mov),
+                    (Bitboard::from_board(&moved).unwrap(), score)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn largest_tiles_stay_apart() {
+        let rows = board([[32768, 32768, 4, 4], [0; 4], [0; 4], [0; 4]]);
+        let packed = Bitboard::from_board(&rows).unwrap();
+        let (moved, score) = packed.slide(Move::Right);
+        assert_eq!(
+            moved.to_board(),
+            board([[0, 32768, 32768, 8], [0; 4], [0; 4], [0; 4]])
+        );
+        assert_eq!(score, 8);
+        assert_eq!(packed.slide(Move::Left).1, 8);
+        assert!(packed.overflows(Move::Right));
+        assert!(packed.overflows(Move::Left));
+        assert!(!packed.overflows(Move::Up));
+        assert!(packed.transpose().overflows(Move::Down));
+    }
+
+    #[test]
+    fn merging_largest_tiles_is_left_to_games() {
+        let rows = board([
+            [32768, 32768, 2, 4],
+            [2, 4, 8, 16],
+            [4, 8, 16, 32],
+            [8, 16, 32, 64],
+        ]);
+        let mut game = Game::from_board(rows.clone(), Tile::TWO_THOUSAND_FORTY_EIGHT, 5).unwrap();
+        game.keep_playing();
+        let mut fast = BitGame::from_game(&game).unwrap();
+
+        // Only the 32768s can merge, so the board isn't locked
+        assert_eq!(fast.status(), game.status());
+        assert_eq!(
+            fast.board().legal_moves().collect::<Vec<_>>(),
+            legal_moves(game.board()).collect::<Vec<_>>()
+        );
+        assert_eq!(fast.mover(Move::Up), Ok(false));
+        assert_eq!(fast.mover(Move::Left), Err(Overflow));
+        assert_eq!(fast.board().to_board(), rows);
+        assert_eq!(fast.moves(), 0);
+
+        let mut taken_back = fast.to_game();
+        assert_eq!(taken_back.board(), game.board());
+        assert_eq!(taken_back.status(), game.status());
+        assert_eq!(taken_back.seed(), game.seed());
+        assert!(game.mover(Move::Left));
+        assert!(taken_back.mover(Move::Left));
+        game.refresh();
+        taken_back.refresh();
+        assert_eq!(taken_back.board(), game.board());
+        assert_eq!(taken_back.score(), game.score());
+        assert_eq!(game.board()[0][0].score(), 65536);
+    }
+
+    /// Plays the same random moves on both engines, checking they agree after every step
+    fn play_both(mut game: Game, mut fast: BitGame, moves_seed: u64) {
+        let mut rng = GameRng::seed_from_u64(moves_seed);
+        loop {
+            assert_eq!(fast.board().to_board(), *game.board());
+            assert_eq!(fast.score(), game.score());
+            assert_eq!(fast.moves(), game.moves());
+            assert_eq!(fast.status(), game.status());
+            assert_eq!(
+                fast.board().legal_moves().collect::<Vec<_>>(),
+                legal_moves(game.board()).collect::<Vec<_>>()
+            );
+            match game.status() {
+                Status::Lost(_) => return,
+                Status::Won => {
+                    game.keep_playing();
+                    fast.keep_playing();
+                }
+                Status::On | Status::WonContinuing => {}
+            }
+
+            let mov = Move::ALL[rng.random_range(0..Move::ALL.len())];
+            let moved = game.mover(mov);
+            assert_eq!(fast.mover(mov), Ok(moved));
+            if moved {
+                game.refresh();
+                fast.refresh();
+            }
+        }
+    }
+
+    #[test]
+    fn bit_games_play_like_games() {
+        for seed in 0..200 {
+            play_both(
+                Game::with_seed(4, Tile::ONE_HUNDRED_TWENTY_EIGHT, seed),
+                BitGame::with_seed(Tile::ONE_HUNDRED_TWENTY_EIGHT, seed),
+                seed,
+            );
+        }
+
+        let rules = SpawnRules::new(vec![(Tile::TWO, 1.0), (Tile::EIGHT, 2.0)], 2, 3).unwrap();
+        for seed in 0..50 {
+            let mut game = Game::with_rules(4, Tile::TWO_THOUSAND_FORTY_EIGHT, rules.clone(), seed);
+            let mut fast =
+                BitGame::with_rules(Tile::TWO_THOUSAND_FORTY_EIGHT, rules.clone(), seed).unwrap();
+            game.limit_moves(60);
+            fast.limit_moves(60);
+            play_both(game, fast, seed + 1000);
+        }
+    }
+
+    #[test]
+    fn games_are_taken_over_mid_game() {
+        let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 8);
+        for mov in [Move::Left, Move::Down, Move::Right, Move::Down, Move::Left] {
+            if game.mover(mov) {
+                game.refresh();
+            }
+        }
+        let fast = BitGame::from_game(&game).unwrap();
+        play_both(game, fast, 3);
+
+        assert!(
+            BitGame::from_game(&Game::with_seed(5, Tile::TWO_THOUSAND_FORTY_EIGHT, 8)).is_none()
+        );
+        let mut evil = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 8);
+        evil.set_spawner(crate::spawn::builtin("evil").unwrap());
+        assert!(BitGame::from_game(&evil).is_none());
+        let puzzle = crate::puzzle::builtin().remove(0).game();
+        assert!(BitGame::from_game(&puzzle).is_none());
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+
+/// Clock of a time-attack game, it starts running as soon as it is created
+pub struct Countdown {
+    deadline: Instant,
+}
+
+impl Countdown {
+    pub fn new(limit: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + limit,
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    /// Time until the clock shows the next second, so it can be redrawn right then
+    pub fn next_tick(&self) -> Duration {
+        next_tick(self.remaining())
+    }
+}
+
+/// Clocks count whole seconds rounded up, the next tick is when the fraction runs out
+fn next_tick(remaining: Duration) -> Duration {
+    match remaining.subsec_nanos() {
+        0 if remaining.is_zero() => Duration::ZERO,
+        0 => Duration::from_secs(1),
+        nanos => Duration::from_nanos(nanos.into()),
+    }
+}
+
+/// Formats the time as the clock shows it, e.g. 1:05, seconds are rounded up
+pub fn format(remaining: Duration) -> String {
+    let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clock_rounds_up() {
+        assert_eq!(format(Duration::from_secs(120)), "2:00");
+        assert_eq!(format(Duration::from_millis(64_200)), "1:05");
+        assert_eq!(format(Duration::from_millis(300)), "0:01");
+        assert_eq!(format(Duration::ZERO), "0:00");
+    }
+
+    #[test]
+    fn ticks_on_whole_seconds() {
+        assert_eq!(
+            next_tick(Duration::from_millis(64_200)),
+            Duration::from_millis(200)
+        );
+        assert_eq!(next_tick(Duration::from_secs(3)), Duration::from_secs(1));
+        assert_eq!(next_tick(Duration::ZERO), Duration::ZERO);
+    }
+}
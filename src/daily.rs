@@ -0,0 +1,155 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use time::Date;
+use twozero48::{Game, Status};
+
+use crate::storage;
+
+const DAILY_FILE: &str = "daily.json";
+
+/// Today's date in UTC, the daily challenge changes at midnight UTC for everyone
+pub fn today() -> Date {
+    time::OffsetDateTime::now_utc().date()
+}
+
+/// Seed of the challenge of the date, its digits spell the date, e.g. 20261018
+pub fn seed(date: Date) -> u64 {
+    date.year() as u64 * 10_000 + u8::from(date.month()) as u64 * 100 + date.day() as u64
+}
+
+/// The official attempt at a daily challenge, recorded when the game starts and updated
+/// when it ends or is quit
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attempt {
+    pub date: String,
+    pub score: usize,
+    pub largest_tile: usize,
+    pub moves: usize,
+    /// False if the game was quit before it was over
+    pub finished: bool,
+}
+
+impl Attempt {
+    pub fn of(date: Date, game: &Game) -> Self {
+        Self {
+            date: date.to_string(),
+            score: game.score(),
+            largest_tile: game.largest_tile().score(),
+            moves: game.moves(),
+            finished: !matches!(game.status(), Status::On | Status::WonContinuing),
+        }
+    }
+
+    /// One line summary to share with others who played the same challenge
+    pub fn share_line(&self) -> String {
+        format!(
+            "twozero48 daily {}: {} points, {} tile, {} moves{}",
+            self.date,
+            self.score,
+            self.largest_tile,
+            self.moves,
+            if self.finished { "" } else { " (quit)" }
+        )
+    }
+}
+
+/// Daily challenges played so far, at most one attempt per date
+#[derive(Default, Serialize, Deserialize)]
+pub struct Attempts {
+    attempts: Vec<Attempt>,
+}
+
+impl Attempts {
+    pub fn path() -> io::Result<PathBuf> {
+        Ok(storage::data_dir()?.join(DAILY_FILE))
+    }
+
+    /// Reads the attempts, a missing file means none were made yet
+    pub fn load(path: &Path) -> io::Result<Self> {
+        storage::load_json(path)
+    }
+
+    pub fn store(&self, path: &Path) -> io::Result<()> {
+        storage::store_json(self, path)
+    }
+
+    /// The attempt made at the challenge of the date, if any
+    pub fn on(&self, date: Date) -> Option<&Attempt> {
+        let date = date.to_string();
+        self.attempts.iter().find(|attempt| attempt.date == date)
+    }
+
+    /// Records the attempt, output bool is false if that date was already attempted
+    pub fn record(&mut self, attempt: Attempt) -> bool {
+        if self.attempts.iter().any(|made| made.date == attempt.date) {
+            return false;
+        }
+        self.attempts.push(attempt);
+
+        true
+    }
+
+    /// Replaces the attempt recorded for the same date with how it went since
+    pub fn update(&mut self, attempt: Attempt) {
+        match self
+            .attempts
+            .iter_mut()
+            .find(|made| made.date == attempt.date)
+        {
+            Some(made) => *made = attempt,
+            None => self.attempts.push(attempt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Month;
+
+    use super::*;
+
+    fn date() -> Date {
+        Date::from_calendar_date(2026, Month::October, 18).unwrap()
+    }
+
+    #[test]
+    fn seed_spells_the_date() {
+        assert_eq!(seed(date()), 20261018);
+        assert_ne!(seed(date()), seed(date().next_day().unwrap()));
+    }
+
+    #[test]
+    fn one_attempt_per_day() {
+        let attempt = Attempt {
+            date: date().to_string(),
+            score: 2048,
+            largest_tile: 256,
+            moves: 180,
+            finished: true,
+        };
+        let mut attempts = Attempts::default();
+        assert!(attempts.record(attempt.clone()));
+        assert!(!attempts.record(Attempt {
+            score: 4096,
+            ..attempt.clone()
+        }));
+        assert_eq!(attempts.on(date()), Some(&attempt));
+        assert_eq!(attempts.on(date().next_day().unwrap()), None);
+
+        let finished = Attempt {
+            score: 3000,
+            ..attempt.clone()
+        };
+        attempts.update(finished.clone());
+        assert_eq!(attempts.on(date()), Some(&finished));
+        assert_eq!(attempts.attempts.len(), 1);
+        assert_eq!(
+            attempt.share_line(),
+            "twozero48 daily 2026-10-18: 2048 points, 256 tile, 180 moves"
+        );
+    }
+}
@@ -0,0 +1,571 @@
+//! Line-oriented JSON protocol to play a [`Game`] from another process, e.g. a bot written in
+//! another language. Every request is one JSON object on its own line, named by its `cmd`:
+//!
+//! ```json
+//! {"cmd": "new", "width": 4, "height": 4, "winning": 2048, "seed": 7, "spawner": "random"}
+//! {"cmd": "move", "dir": "left"}
+//! {"cmd": "undo"}
+//! {"cmd": "state"}
+//! {"cmd": "quit"}
+//! ```
+//!
+//! Every field of `new` is optional, `spawn` takes the weights as `"2:0.9,4:0.1"` and
+//! `spawn_count`, `start_tiles` and `move_limit` are numbers. Each request is answered with one
+//! line, the state of the game, an error or a goodbye to `quit`:
+//!
+//! ```json
+//! {"type": "state", "board": [[2, 0], [0, 0]], "score": 0, "status": "on", "legal": ["right", "down"], ...}
+//! {"type": "error", "code": "illegal_move", "message": "left doesn't change the board"}
+//! {"type": "bye"}
+//! ```
+//!
+//! A game starts right away and its state is sent before the first request. A won game
+//! continues with the next move.
+
+use std::io::{self, BufRead, Write};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    Game, LossReason, Move, Size, Status, Tile, legal_moves,
+    spawn::{self, SpawnRules},
+};
+
+/// Largest width or height of a board, so a request can't make the engine allocate a huge one
+pub const MAX_SIDE: usize = 16;
+
+/// Rules of the games a [`Session`] starts, a `new` request can change any of them
+#[derive(Clone, Debug)]
+pub struct GameOptions {
+    pub size: Size,
+    pub winning: Tile,
+    pub rules: SpawnRules,
+    /// Name of a built-in spawner
+    pub spawner: String,
+    /// Seed of the first game, later games are seeded at random unless `new` gives a seed
+    pub seed: Option<u64>,
+    pub move_limit: Option<usize>,
+}
+
+impl GameOptions {
+    /// Starts a game by the options, an error if they don't make a valid game
+    pub fn game(&self) -> Result<Game, String> {
+        start(self, NewGame::default())
+    }
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        Self {
+            size: 4.into(),
+            winning: Tile::TWO_THOUSAND_FORTY_EIGHT,
+            rules: SpawnRules::default(),
+            spawner: "random".to_string(),
+            seed: None,
+            move_limit: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "lowercase", deny_unknown_fields)]
+enum Request {
+    New(NewGame),
+    Move { dir: Move },
+    Undo,
+    State,
+    Quit,
+}
+
+/// Commands a client can send, anything else is an unknown command
+const COMMANDS: [&str; 5] = ["new", "move", "undo", "state", "quit"];
+
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewGame {
+    width: Option<usize>,
+    height: Option<usize>,
+    winning: Option<usize>,
+    seed: Option<u64>,
+    spawn: Option<String>,
+    spawn_count: Option<usize>,
+    start_tiles: Option<usize>,
+    spawner: Option<String>,
+    move_limit: Option<usize>,
+}
+
+/// One line sent back to the client
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Response {
+    State(GameState),
+    Error { code: ErrorCode, message: String },
+    Bye,
+}
+
+/// What went wrong with a request
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The line isn't a JSON object with a `cmd`
+    InvalidJson,
+    UnknownCommand,
+    /// The command is known but its fields are missing or wrong
+    InvalidArgument,
+    /// The move doesn't change the board, or the game is over
+    IllegalMove,
+    NothingToUndo,
+    /// The command isn't allowed in this session
+    Forbidden,
+    /// No request came in time, the session is over
+    TimedOut,
+    /// The server is hosting as many games as it can, try again later
+    Busy,
+}
+
+/// Everything a client needs to pick its next move
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameState {
+    /// Tiles by value, 0 for empty cells and `null` for blocked ones
+    pub board: Vec<Vec<Option<usize>>>,
+    pub score: usize,
+    pub moves: usize,
+    /// `on`, `won`, `won_continuing` or `lost`
+    pub status: String,
+    /// Why the game was lost, `locked` or `out_of_moves`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Moves that change the board, empty once the game is over
+    pub legal: Vec<Move>,
+    pub winning: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moves_left: Option<usize>,
+}
+
+impl GameState {
+    pub fn of(game: &Game) -> Self {
+        let (status, reason) = match game.status() {
+            Status::On => ("on", None),
+            Status::Won => ("won", None),
+            Status::WonContinuing => ("won_continuing", None),
+            Status::Lost(LossReason::Locked) => ("lost", Some("locked")),
+            Status::Lost(LossReason::OutOfMoves) => ("lost", Some("out_of_moves")),
+        };
+        let over = matches!(game.status(), Status::Lost(_));
+
+        Self {
+            board: game
+                .board()
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|tile| (!tile.is_blocked()).then(|| tile.score()))
+                        .collect()
+                })
+                .collect(),
+            score: game.score(),
+            moves: game.moves(),
+            status: status.to_string(),
+            reason: reason.map(str::to_string),
+            legal: if over {
+                vec![]
+            } else {
+                legal_moves(game.board()).collect()
+            },
+            winning: game.winning().score(),
+            seed: game.seed(),
+            moves_left: game.moves_left(),
+        }
+    }
+}
+
+impl Response {
+    pub fn error(code: ErrorCode, message: impl Into<String>) -> Self {
+        Response::Error {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// A client's game, along with the rules of the games it starts
+pub struct Session {
+    game: Game,
+    options: GameOptions,
+    /// Whether the client may start new games and undo moves
+    free: bool,
+}
+
+impl Session {
+    /// Starts the first game, an error if the options don't make a valid game
+    pub fn new(mut options: GameOptions) -> Result<Self, String> {
+        let game = options.game()?;
+        // The seed is spent on the first game, later ones are seeded at random
+        options.seed = None;
+
+        Ok(Self {
+            game,
+            options,
+            free: true,
+        })
+    }
+
+    /// Plays the given game, `new` and `undo` are refused so the client plays it to the end
+    pub fn restricted(game: Game) -> Self {
+        Self {
+            game,
+            options: GameOptions::default(),
+            free: false,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// Ends the session, returning the game as it was left
+    pub fn into_game(self) -> Game {
+        self.game
+    }
+
+    pub fn state(&self) -> Response {
+        Response::State(GameState::of(&self.game))
+    }
+
+    /// Answers one request line
+    pub fn handle(&mut self, line: &str) -> Response {
+        let value: Value = match serde_json::from_str(line) {
+            Ok(value @ Value::Object(_)) => value,
+            Ok(_) => return Response::error(ErrorCode::InvalidJson, "requests are JSON objects"),
+            Err(e) => return Response::error(ErrorCode::InvalidJson, e.to_string()),
+        };
+        match value.get("cmd").and_then(Value::as_str) {
+            None => {
+                return Response::error(ErrorCode::InvalidJson, "requests need a cmd field");
+            }
+            Some(cmd) if !COMMANDS.contains(&cmd) => {
+                return Response::error(
+                    ErrorCode::UnknownCommand,
+                    format!(
+                        "{cmd} is not a command, use one of: {}",
+                        COMMANDS.join(", ")
+                    ),
+                );
+            }
+            Some(_) => {}
+        }
+        let request: Request = match serde_json::from_value(value) {
+            Ok(request) => request,
+            Err(e) => return Response::error(ErrorCode::InvalidArgument, e.to_string()),
+        };
+
+        match request {
+            Request::New(_) | Request::Undo if !self.free => Response::error(
+                ErrorCode::Forbidden,
+                "this game has to be played to the end",
+            ),
+            Request::New(new) => match start(&self.options, new) {
+                Ok(game) => {
+                    self.game = game;
+                    self.state()
+                }
+                Err(e) => Response::error(ErrorCode::InvalidArgument, e),
+            },
+            Request::Move { dir: Move::Dont } => {
+                Response::error(ErrorCode::InvalidArgument, "dir is left, right, up or down")
+            }
+            Request::Move { dir } => {
+                if let Status::Lost(_) = self.game.status() {
+                    return Response::error(ErrorCode::IllegalMove, "the game is over");
+                }
+                if self.game.status() == Status::Won {
+                    self.game.keep_playing();
+                }
+                if self.game.mover(dir) {
+                    self.game.refresh();
+                    self.state()
+                } else {
+                    Response::error(
+                        ErrorCode::IllegalMove,
+                        format!("{} doesn't change the board", name(dir)),
+                    )
+                }
+            }
+            Request::Undo => {
+                if self.game.undo() {
+                    self.state()
+                } else {
+                    Response::error(ErrorCode::NothingToUndo, "no move to undo")
+                }
+            }
+            Request::State => self.state(),
+            Request::Quit => Response::Bye,
+        }
+    }
+}
+
+/// Lowercase name of the move, as the protocol spells it
+fn name(mov: Move) -> String {
+    format!("{mov:?}").to_lowercase()
+}
+
+/// Starts a game by the options, overridden by the fields of a `new` request
+fn start(options: &GameOptions, new: NewGame) -> Result<Game, String> {
+    let size = Size {
+        width: new.width.unwrap_or(options.size.width),
+        height: new.height.unwrap_or(options.size.height),
+    };
+    if size.width < 2 || size.height < 2 {
+        return Err("the board has to be 2x2 or more".to_string());
+    }
+    if size.width > MAX_SIDE || size.height > MAX_SIDE {
+        return Err(format!("the board can be at most {MAX_SIDE}x{MAX_SIDE}"));
+    }
+    let winning = match new.winning {
+        Some(value) => Tile::from_score(value)
+            .filter(|&tile| tile > Tile::TWO)
+            .ok_or_else(|| format!("{value} is not a winning tile, e.g. 2048"))?,
+        None => options.winning,
+    };
+    let rules = if new.spawn.is_some() || new.spawn_count.is_some() || new.start_tiles.is_some() {
+        let weights = match &new.spawn {
+            Some(weights) => spawn::parse_weights(weights)?,
+            None => options.rules.weights().to_vec(),
+        };
+        SpawnRules::new(
+            weights,
+            new.spawn_count.unwrap_or(options.rules.count()),
+            new.start_tiles.unwrap_or(options.rules.start_tiles()),
+        )?
+    } else {
+        options.rules.clone()
+    };
+    let spawner_name = new.spawner.as_deref().unwrap_or(&options.spawner);
+    let spawner = spawn::builtin(spawner_name).ok_or_else(|| {
+        format!(
+            "{spawner_name} is not a spawner, use one of: {}",
+            spawn::BUILTIN.join(", ")
+        )
+    })?;
+    let seed = new.seed.or(options.seed).unwrap_or_else(rand::random);
+
+    let mut game = Game::with_rules(size, winning, rules, seed);
+    game.set_spawner(spawner);
+    if let Some(limit) = new.move_limit.or(options.move_limit) {
+        game.limit_moves(limit);
+    }
+
+    Ok(game)
+}
+
+/// Speaks the protocol over a pair of streams until the client quits or closes the input.
+/// The state of the first game is sent before the first request.
+pub fn serve<Rd: BufRead, W: Write>(
+    session: &mut Session,
+    reader: Rd,
+    mut writer: W,
+) -> io::Result<()> {
+    send(&mut writer, &session.state())?;
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = session.handle(&line);
+        send(&mut writer, &response)?;
+        if response == Response::Bye {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the response as a line of its own and flushes it, so the client sees it right away
+pub fn send<W: Write>(writer: &mut W, response: &Response) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, response).map_err(io::Error::other)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Session {
+        Session::new(GameOptions {
+            seed: Some(3),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn error_code(response: Response) -> ErrorCode {
+        match response {
+            Response::Error { code, .. } => code,
+            response => panic!("expected an error, got {response:?}"),
+        }
+    }
+
+    fn state(response: Response) -> GameState {
+        match response {
+            Response::State(state) => state,
+            response => panic!("expected a state, got {response:?}"),
+        }
+    }
+
+    #[test]
+    fn moves_follow_the_game() {
+        let mut session = session();
+        let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 3);
+        let first = state(session.state());
+        assert_eq!(first.moves, 0);
+        assert_eq!(first.status, "on");
+
+        let mov = first.legal[0];
+        assert!(game.mover(mov));
+        game.refresh();
+        let next = state(session.handle(&format!(r#"{{"cmd": "move", "dir": "{}"}}"#, name(mov))));
+        assert_eq!(next, GameState::of(&game));
+
+        assert_eq!(state(session.handle(r#"{"cmd": "undo"}"#)), first);
+        assert_eq!(
+            error_code(session.handle(r#"{"cmd": "undo"}"#)),
+            ErrorCode::NothingToUndo
+        );
+        assert_eq!(session.handle(r#"{"cmd": "quit"}"#), Response::Bye);
+    }
+
+    #[test]
+    fn bad_requests_get_structured_errors() {
+        let mut session = session();
+        for (line, code) in [
+            ("not json", ErrorCode::InvalidJson),
+            ("[1, 2]", ErrorCode::InvalidJson),
+            (r#"{"dir": "left"}"#, ErrorCode::InvalidJson),
+            (r#"{"cmd": "fly"}"#, ErrorCode::UnknownCommand),
+            (r#"{"cmd": "move"}"#, ErrorCode::InvalidArgument),
+            (
+                r#"{"cmd": "move", "dir": "sideways"}"#,
+                ErrorCode::InvalidArgument,
+            ),
+            (
+                r#"{"cmd": "move", "dir": "dont"}"#,
+                ErrorCode::InvalidArgument,
+            ),
+            (r#"{"cmd": "new", "width": 1}"#, ErrorCode::InvalidArgument),
+            (
+                r#"{"cmd": "new", "spawner": "psychic"}"#,
+                ErrorCode::InvalidArgument,
+            ),
+            (
+                r#"{"cmd": "new", "colour": "red"}"#,
+                ErrorCode::InvalidArgument,
+            ),
+        ] {
+            assert_eq!(error_code(session.handle(line)), code, "{line}");
+        }
+
+        let board = vec![vec![Tile::TWO, Tile::FOUR], vec![Tile::EMPTY, Tile::EMPTY]];
+        let mut session = Session::restricted(
+            Game::from_board(board, Tile::TWO_THOUSAND_FORTY_EIGHT, 0).unwrap(),
+        );
+        assert_eq!(
+            error_code(session.handle(r#"{"cmd": "move", "dir": "left"}"#)),
+            ErrorCode::IllegalMove
+        );
+    }
+
+    #[test]
+    fn new_games_take_options() {
+        let mut session = session();
+        let game = state(session.handle(
+            r#"{"cmd": "new", "width": 5, "height": 3, "seed": 9, "spawn": "8:1", "start_tiles": 3, "move_limit": 10}"#,
+        ));
+        assert_eq!((game.board.len(), game.board[0].len()), (3, 5));
+        assert_eq!(game.seed, Some(9));
+        assert_eq!(game.moves_left, Some(10));
+        let tiles: Vec<usize> = game
+            .board
+            .iter()
+            .flatten()
+            .flatten()
+            .copied()
+            .filter(|&v| v > 0)
+            .collect();
+        assert_eq!(tiles, vec![8, 8, 8]);
+    }
+
+    #[test]
+    fn huge_boards_are_refused() {
+        let mut session = session();
+        assert_eq!(
+            error_code(session.handle(r#"{"cmd": "new", "width": 100000, "height": 100000}"#)),
+            ErrorCode::InvalidArgument
+        );
+        let side = MAX_SIDE + 1;
+        assert_eq!(
+            error_code(session.handle(&format!(r#"{{"cmd": "new", "height": {side}}}"#))),
+            ErrorCode::InvalidArgument
+        );
+        let game = state(session.handle(&format!(
+            r#"{{"cmd": "new", "width": {MAX_SIDE}, "height": {MAX_SIDE}}}"#
+        )));
+        assert_eq!(game.board.len(), MAX_SIDE);
+    }
+
+    #[test]
+    fn only_the_first_game_takes_the_option_seed() {
+        let mut session = session();
+        assert_eq!(state(session.state()).seed, Some(3));
+        let second = state(session.handle(r#"{"cmd": "new"}"#)).seed;
+        let third = state(session.handle(r#"{"cmd": "new"}"#)).seed;
+        assert_ne!(second, Some(3));
+        assert_ne!(second, third);
+        assert_eq!(
+            state(session.handle(r#"{"cmd": "new", "seed": 3}"#)).seed,
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn restricted_sessions_play_one_game() {
+        let mut session =
+            Session::restricted(Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 1));
+        assert_eq!(
+            error_code(session.handle(r#"{"cmd": "new"}"#)),
+            ErrorCode::Forbidden
+        );
+        assert_eq!(
+            error_code(session.handle(r#"{"cmd": "undo"}"#)),
+            ErrorCode::Forbidden
+        );
+    }
+
+    #[test]
+    fn serve_answers_every_line() {
+        let mut session = session();
+        let input = "{\"cmd\": \"state\"}\n\n{\"cmd\": \"fly\"}\n{\"cmd\": \"quit\"}\n{\"cmd\": \"state\"}\n";
+        let mut output = vec![];
+        serve(&mut session, input.as_bytes(), &mut output).unwrap();
+
+        let responses: Vec<Response> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 4);
+        assert_eq!(responses[0], responses[1]);
+        assert!(matches!(
+            responses[2],
+            Response::Error {
+                code: ErrorCode::UnknownCommand,
+                ..
+            }
+        ));
+        assert_eq!(responses[3], Response::Bye);
+    }
+}
@@ -0,0 +1,113 @@
+use rand::Rng;
+use twozero48::{
+    Board, Move, Size, Spawn, Tile,
+    spawn::{SpawnRules, Spawner},
+};
+
+/// Stands in for the random spawns in hot-seat games, player two places every tile
+/// after the move instead
+pub struct PlayerTwo;
+
+impl Spawner for PlayerTwo {
+    fn name(&self) -> &str {
+        "player two"
+    }
+
+    fn spawn(&mut self, _: &Board, _: &SpawnRules, _: &mut dyn Rng) -> Option<Spawn> {
+        None
+    }
+}
+
+/// Cell and tile player two is about to place
+pub struct Placement {
+    cursor: (usize, usize),
+    tiles: Vec<Tile>,
+    tile: usize,
+}
+
+impl Placement {
+    /// Starts on the first empty cell with the first tile the rules allow
+    pub fn new(board: &Board, rules: &SpawnRules) -> Self {
+        let cursor = board
+            .iter()
+            .enumerate()
+            .find_map(|(r, row)| row.iter().position(|&t| t == Tile::EMPTY).map(|c| (r, c)))
+            .unwrap_or((0, 0));
+
+        Self {
+            cursor,
+            tiles: rules.weights().iter().map(|&(tile, _)| tile).collect(),
+            tile: 0,
+        }
+    }
+
+    /// Moves the cursor one cell in the direction, it stops at the edges of the board
+    pub fn move_cursor(&mut self, mov: Move, size: Size) {
+        let (r, c) = &mut self.cursor;
+        match mov {
+            Move::Left => *c = c.saturating_sub(1),
+            Move::Right => *c = (*c + 1).min(size.width - 1),
+            Move::Up => *r = r.saturating_sub(1),
+            Move::Down => *r = (*r + 1).min(size.height - 1),
+            Move::Dont => {}
+        }
+    }
+
+    /// Switches to the next tile the rules allow
+    pub fn next_tile(&mut self) {
+        self.tile = (self.tile + 1) % self.tiles.len();
+    }
+
+    /// Switches to the tile, output bool is false if the rules don't allow it
+    pub fn choose(&mut self, tile: Tile) -> bool {
+        match self.tiles.iter().position(|&allowed| allowed == tile) {
+            Some(i) => {
+                self.tile = i;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn spawn(&self) -> Spawn {
+        Spawn {
+            at: self.cursor,
+            tile: self.tiles[self.tile],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_stays_on_the_board() {
+        let mut board = vec![vec![Tile::TWO; 3]; 2];
+        board[0][2] = Tile::EMPTY;
+        let mut placement = Placement::new(&board, &SpawnRules::default());
+        assert_eq!(placement.spawn().at, (0, 2));
+
+        placement.move_cursor(Move::Right, Size::from((3, 2)));
+        placement.move_cursor(Move::Up, Size::from((3, 2)));
+        assert_eq!(placement.spawn().at, (0, 2));
+        for _ in 0..3 {
+            placement.move_cursor(Move::Down, Size::from((3, 2)));
+            placement.move_cursor(Move::Left, Size::from((3, 2)));
+        }
+        assert_eq!(placement.spawn().at, (1, 0));
+    }
+
+    #[test]
+    fn tiles_come_from_the_rules() {
+        let mut placement = Placement::new(&vec![vec![Tile::EMPTY; 2]; 2], &SpawnRules::default());
+        assert_eq!(placement.spawn().tile, Tile::TWO);
+        placement.next_tile();
+        assert_eq!(placement.spawn().tile, Tile::FOUR);
+        placement.next_tile();
+        assert_eq!(placement.spawn().tile, Tile::TWO);
+        assert!(placement.choose(Tile::FOUR));
+        assert!(!placement.choose(Tile::EIGHT));
+        assert_eq!(placement.spawn().tile, Tile::FOUR);
+    }
+}
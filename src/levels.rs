@@ -0,0 +1,58 @@
+use std::io;
+
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use twozero48::puzzle::Puzzle;
+
+use crate::tui::TermGuard;
+
+/// What the puzzle asks for, e.g. "reach 32 in 5 moves"
+pub fn summary(puzzle: &Puzzle) -> String {
+    match puzzle.moves() {
+        Some(1) => format!("{} in 1 move", puzzle.goal()),
+        Some(moves) => format!("{} in {moves} moves", puzzle.goal()),
+        None => puzzle.goal().to_string(),
+    }
+}
+
+/// Shows the level-select screen, `None` if the player quit without picking a puzzle
+pub fn select(mut puzzles: Vec<Puzzle>) -> io::Result<Option<Puzzle>> {
+    let items: Vec<(&str, String)> = puzzles
+        .iter()
+        .map(|puzzle| (puzzle.name.as_str(), summary(puzzle)))
+        .collect();
+    let mut terminal = TermGuard::new()?;
+    let mut selected = 0;
+
+    let picked = loop {
+        terminal.render_menu("PUZZLES", &items, selected, &puzzles[selected].description)?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('q'), _)
+            | (KeyCode::Char('Q'), _)
+            | (KeyCode::Char('c'), KeyModifiers::CONTROL)
+            | (KeyCode::Esc, _) => break None,
+            (KeyCode::Enter, _) => break Some(selected),
+            (KeyCode::Char('w'), _) | (KeyCode::Char('W'), _) | (KeyCode::Up, _) => {
+                selected = selected.checked_sub(1).unwrap_or(items.len() - 1);
+            }
+            (KeyCode::Char('s'), _) | (KeyCode::Char('S'), _) | (KeyCode::Down, _) => {
+                selected = (selected + 1) % items.len();
+            }
+            (KeyCode::Char(digit), _) => {
+                if let Some(i) = digit
+                    .to_digit(10)
+                    .and_then(|n| (n as usize).checked_sub(1))
+                    .filter(|&i| i < items.len())
+                {
+                    selected = i;
+                }
+            }
+            _ => {}
+        }
+    };
+
+    Ok(picked.map(|i| puzzles.swap_remove(i)))
+}
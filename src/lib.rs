@@ -1,9 +1,22 @@
-use std::fmt::Display;
+use std::{collections::VecDeque, fmt::Display};
 
-use rand::prelude::*;
+use puzzle::Goal;
+use rand::{prelude::*, rngs::Xoshiro256PlusPlus};
+use serde::{Deserialize, Serialize};
+use spawn::{RandomSpawner, SpawnRules, Spawner};
+
+pub mod bitboard;
+pub mod engine;
+pub mod puzzle;
+pub mod record;
+pub mod save;
+pub mod solver;
+pub mod spawn;
+pub mod strategy;
 
 /// Used to depict user choice, an input to the [`Game`] API
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Move {
     /// Executes leftward compression of board elements
     Left,
@@ -17,201 +30,492 @@ pub enum Move {
     Dont,
 }
 
+impl Move {
+    /// The directions a tile can be moved in
+    pub const ALL: [Move; 4] = [Move::Left, Move::Right, Move::Up, Move::Down];
+}
+
 /// Used to depict the status in the [`Game`] API
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Status {
-    /// Game has finished, player won
+    /// Game has finished, player won, unless they choose to [`Game::keep_playing`]
     Won,
-    /// Game has finished, player lost
-    Lost,
+    /// Game continues after the player won, until it is lost
+    WonContinuing,
+    /// Game has finished for the given reason, see [`Game::has_won`] for whether it was won before
+    Lost(LossReason),
     /// Game continues, neither won nor lost
     On,
 }
 
-/// Represents a tile(value) on the game board.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
-pub enum Tile {
-    #[default]
-    Empty,
-    Two,
-    Four,
-    Eight,
-    Sixteen,
-    ThirtyTwo,
-    SixtyFour,
-    OneHundredTwentyEight,
-    TwoHundredFiftySix,
-    FiveHundredTwelve,
-    OneThousandTwentyFour,
-    TwoThousandFourtyEight,
-    FourHundredNinetySix,
+/// Why a [`Game`] ended with [`Status::Lost`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LossReason {
+    /// The board is full and no tiles can merge
+    Locked,
+    /// Every move allowed by [`Game::limit_moves`] was played
+    OutOfMoves,
 }
 
+/// Represents a tile(value) on the game board, stored as the exponent of its value,
+/// e.g. 2048 = 2^11 is stored as 11 and the empty tile as 0. Blocked cells are a tile too,
+/// see [`Tile::BLOCKED`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Tile(u8);
+
 impl Tile {
-    /// Returns the score value of the tile.
-    fn score(&self) -> usize {
-        match self {
-            Tile::Empty => 0,
-            Tile::Two => 2,
-            Tile::Four => 4,
-            Tile::Eight => 8,
-            Tile::Sixteen => 16,
-            Tile::ThirtyTwo => 32,
-            Tile::SixtyFour => 64,
-            Tile::OneHundredTwentyEight => 128,
-            Tile::TwoHundredFiftySix => 256,
-            Tile::FiveHundredTwelve => 512,
-            Tile::OneThousandTwentyFour => 1024,
-            Tile::TwoThousandFourtyEight => 2048,
-            Tile::FourHundredNinetySix => 4096,
+    pub const EMPTY: Tile = Tile(0);
+    pub const TWO: Tile = Tile(1);
+    pub const FOUR: Tile = Tile(2);
+    pub const EIGHT: Tile = Tile(3);
+    pub const SIXTEEN: Tile = Tile(4);
+    pub const THIRTY_TWO: Tile = Tile(5);
+    pub const SIXTY_FOUR: Tile = Tile(6);
+    pub const ONE_HUNDRED_TWENTY_EIGHT: Tile = Tile(7);
+    pub const TWO_HUNDRED_FIFTY_SIX: Tile = Tile(8);
+    pub const FIVE_HUNDRED_TWELVE: Tile = Tile(9);
+    pub const ONE_THOUSAND_TWENTY_FOUR: Tile = Tile(10);
+    pub const TWO_THOUSAND_FORTY_EIGHT: Tile = Tile(11);
+    pub const FOUR_THOUSAND_NINETY_SIX: Tile = Tile(12);
+
+    /// A cell no tile can enter, it never moves nor merges and has no value.
+    /// Tiles slide up against it like against the edge of the board.
+    pub const BLOCKED: Tile = Tile(u8::MAX);
+
+    /// Largest exponent a tile can have, so its value still fits in a `usize`
+    pub const MAX_EXPONENT: u8 = usize::BITS as u8 - 1;
+
+    /// Returns the tile with the value 2^exponent, 0 is the empty tile
+    pub fn from_exponent(exponent: u8) -> Option<Self> {
+        (exponent <= Self::MAX_EXPONENT).then_some(Tile(exponent))
+    }
+
+    /// Returns the exponent of the tile's value, 0 for the empty tile and `u8::MAX` for
+    /// [`Tile::BLOCKED`]
+    pub fn exponent(&self) -> u8 {
+        self.0
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        *self == Tile::BLOCKED
+    }
+
+    /// Returns the score value of the tile, 0 for empty and blocked cells
+    pub fn score(&self) -> usize {
+        match self.0 {
+            0 | u8::MAX => 0,
+            exponent => 1 << exponent,
+        }
+    }
+
+    /// Returns the tile with the given score value, `None` if no tile has that value
+    pub fn from_score(score: usize) -> Option<Self> {
+        match score {
+            0 => Some(Tile::EMPTY),
+            1 => None,
+            score if score.is_power_of_two() => Some(Tile(score.trailing_zeros() as u8)),
+            _ => None,
         }
     }
 
-    /// Promotes the tile to the next value, e.g. `Two` becomes `Four`, `Four` becomes `Eight`, etc.
-    /// Empty and 4096 tiles don't change as they are upper limits.
+    /// Promotes the tile to the next value, e.g. 2 becomes 4, 4 becomes 8, etc.
+    /// Empty tiles don't change, neither does the largest tile as it is the upper limit.
     fn promote(&self) -> Self {
-        match self {
-            Tile::Empty => Tile::Empty,
-            Tile::Two => Tile::Four,
-            Tile::Four => Tile::Eight,
-            Tile::Eight => Tile::Sixteen,
-            Tile::Sixteen => Tile::ThirtyTwo,
-            Tile::ThirtyTwo => Tile::SixtyFour,
-            Tile::SixtyFour => Tile::OneHundredTwentyEight,
-            Tile::OneHundredTwentyEight => Tile::TwoHundredFiftySix,
-            Tile::TwoHundredFiftySix => Tile::FiveHundredTwelve,
-            Tile::FiveHundredTwelve => Tile::OneThousandTwentyFour,
-            Tile::OneThousandTwentyFour => Tile::TwoThousandFourtyEight,
-            Tile::TwoThousandFourtyEight => Tile::FourHundredNinetySix,
-            Tile::FourHundredNinetySix => Tile::FourHundredNinetySix,
+        match self.0 {
+            0 => Tile::EMPTY,
+            exponent => Tile((exponent + 1).min(Self::MAX_EXPONENT)),
         }
     }
 }
 
 impl Display for Tile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_blocked() {
+            return write!(f, "#");
+        }
         write!(f, "{}", self.score())
     }
 }
 
-type Board = Vec<Vec<Tile>>;
+/// Grid of tiles the game is played on, indexed by row then column
+pub type Board = Vec<Vec<Tile>>;
+
+/// Dimensions of the board, a single `usize` converts into a square board
+/// and a `(width, height)` pair into a rectangular one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Size {
+    /// Number of columns
+    pub width: usize,
+    /// Number of rows
+    pub height: usize,
+}
+
+impl From<usize> for Size {
+    fn from(board_size: usize) -> Self {
+        Self {
+            width: board_size,
+            height: board_size,
+        }
+    }
+}
+
+impl From<(usize, usize)> for Size {
+    fn from((width, height): (usize, usize)) -> Self {
+        Self { width, height }
+    }
+}
+
+impl Display for Size {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Position of a cell on the board as `(row, column)`
+pub type Cell = (usize, usize);
+
+/// Where a tile was before a move and where it ended up, `from` and `to` are
+/// the same for tiles that didn't move
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TileMove {
+    pub from: Cell,
+    pub to: Cell,
+    /// The tile as it was before the move
+    pub tile: Tile,
+}
+
+/// Two tiles that merged into one, the merged tile sits in the cell `at`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Merge {
+    /// Cells the two tiles came from
+    pub from: [Cell; 2],
+    pub at: Cell,
+    /// The tile they merged into
+    pub tile: Tile,
+}
+
+/// A tile placed on the board after a move
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Spawn {
+    pub at: Cell,
+    pub tile: Tile,
+}
+
+/// Everything that happened during a move played with [`Game::play`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MoveOutcome {
+    pub mov: Move,
+    /// Every tile that was on the board, including the ones that stayed put
+    pub tiles: Vec<TileMove>,
+    pub merges: Vec<Merge>,
+    /// Score earned from the merges
+    pub score: usize,
+    /// Tiles spawned after the move, fewer than [`SpawnRules::count`] if the board filled up
+    pub spawns: Vec<Spawn>,
+}
+
+/// Random number generator used by [`Game`] unless another one is injected,
+/// it is portable across platforms so a seed always yields the same game.
+pub type GameRng = Xoshiro256PlusPlus;
+
+/// Number of moves kept in the history, older moves can't be undone
+const HISTORY_LIMIT: usize = 128;
+
+/// State of the game before/after a move, restored on undo/redo
+#[derive(Clone)]
+struct Snapshot<R> {
+    board: Board,
+    continued: bool,
+    score: usize,
+    moves: usize,
+    spawned: usize,
+    rng: R,
+}
 
 /// An object that models the board to play 2048 on and defines the rules for the game
-pub struct Game {
+pub struct Game<R = GameRng> {
     board: Board,
-    board_size: usize,
+    size: Size,
     winning: Tile,
+    continued: bool,
+    goal: Option<Goal>,
+    rules: SpawnRules,
+    spawner: Box<dyn Spawner + Send>,
+    /// Number of tiles the current spawner placed so far
+    spawned: usize,
     score: usize,
+    moves: usize,
+    rng: R,
+    seed: Option<u64>,
+    undo_stack: VecDeque<Snapshot<R>>,
+    redo_stack: Vec<Snapshot<R>>,
+    undos_left: Option<usize>,
+    move_limit: Option<usize>,
 }
 
 impl Game {
-    /// Constructs a board to play the game
-    /// board_size >= 2, defines board's length & breadth
+    /// Constructs a board to play the game, seeded from the thread's random number generator
+    /// size defines board's width & height, each should be 2 or more
     /// winning defines the Tile for the game to have been won
-    pub fn new(board_size: usize, winning: Tile) -> Self {
-        // Ensure the board size is at least 2
-        let board_size = board_size.clamp(2, usize::MAX);
+    pub fn new(size: impl Into<Size>, winning: Tile) -> Self {
+        Self::with_seed(size, winning, rand::rng().random())
+    }
 
-        // initialize an empty board of 0s
-        let empty = [Tile::Empty].repeat(board_size);
-        let mut board = vec![];
-        board.resize(board_size, empty);
+    /// Constructs a reproducible game, the same seed and sequence of [`Move`]s
+    /// always yields the same boards and score
+    pub fn with_seed(size: impl Into<Size>, winning: Tile, seed: u64) -> Self {
+        Self::with_rules(size, winning, SpawnRules::default(), seed)
+    }
 
-        let mut init = Self {
-            board,
-            board_size,
+    /// Constructs a reproducible game where tiles spawn by the given rules
+    pub fn with_rules(size: impl Into<Size>, winning: Tile, rules: SpawnRules, seed: u64) -> Self {
+        let mut game = Self::empty(size.into(), winning, rules, GameRng::seed_from_u64(seed));
+        game.seed = Some(seed);
+        game.spawn_start_tiles();
+
+        game
+    }
+
+    /// Constructs a reproducible game on the board as it is, e.g. a puzzle, no start tiles
+    /// are spawned. The board has to be a rectangle of 2x2 or more
+    pub fn from_board(board: Board, winning: Tile, seed: u64) -> Result<Self, String> {
+        let height = board.len();
+        let width = board.first().map_or(0, Vec::len);
+        if width < 2 || height < 2 {
+            return Err("the board has to be 2x2 or more".to_string());
+        }
+        if board.iter().any(|row| row.len() != width) {
+            return Err("every row of the board has to be as wide".to_string());
+        }
+
+        let mut game = Self::empty(
+            (width, height).into(),
             winning,
-            score: 0,
-        };
+            SpawnRules::default(),
+            GameRng::seed_from_u64(seed),
+        );
+        game.board = board;
+        game.seed = Some(seed);
 
-        // Spawns first random value
-        init.spawn();
+        Ok(game)
+    }
+}
+
+impl<R: Rng + Clone> Game<R> {
+    /// Constructs a game that draws its spawns from the provided random number generator
+    pub fn with_rng(size: impl Into<Size>, winning: Tile, rng: R) -> Self {
+        let mut init = Self::empty(size.into(), winning, SpawnRules::default(), rng);
+        init.spawn_start_tiles();
 
         init
     }
 
+    /// Constructs a game on an empty board, without spawning any tiles
+    fn empty(size: Size, winning: Tile, rules: SpawnRules, rng: R) -> Self {
+        // Ensure the board is at least 2x2
+        let size = Size {
+            width: size.width.max(2),
+            height: size.height.max(2),
+        };
+
+        // initialize an empty board of 0s
+        let board = vec![vec![Tile::EMPTY; size.width]; size.height];
+
+        Self {
+            board,
+            size,
+            winning,
+            continued: false,
+            goal: None,
+            rules,
+            spawner: Box::new(RandomSpawner),
+            spawned: 0,
+            score: 0,
+            moves: 0,
+            rng,
+            seed: None,
+            undo_stack: VecDeque::new(),
+            redo_stack: vec![],
+            undos_left: None,
+            move_limit: None,
+        }
+    }
+
     /// Return immutable reference to the board
     pub fn board(&self) -> &Board {
         &self.board
     }
 
+    /// Returns the dimensions of the board
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
     /// Returns the Tile for winning
     pub fn winning(&self) -> Tile {
         self.winning
     }
 
+    /// Returns the goal that wins the game instead of the winning tile, if any
+    pub fn goal(&self) -> Option<Goal> {
+        self.goal
+    }
+
+    /// Wins the game once the goal is met, instead of once the winning tile is reached
+    pub fn set_goal(&mut self, goal: Goal) {
+        self.goal = Some(goal);
+    }
+
+    /// Returns the rules tiles spawn by
+    pub fn spawn_rules(&self) -> &SpawnRules {
+        &self.rules
+    }
+
+    /// Returns the spawner placing the tiles
+    pub fn spawner(&self) -> &dyn Spawner {
+        self.spawner.as_ref()
+    }
+
+    /// Places the tiles spawned from now on with the spawner instead of at random
+    pub fn set_spawner(&mut self, spawner: Box<dyn Spawner + Send>) {
+        self.spawner = spawner;
+        self.spawned = 0;
+    }
+
     /// Returns the current score
     pub fn score(&self) -> usize {
         self.score
     }
 
-    /// Performs the compression of board's values towards the left most column
-    fn move_left(&mut self) {
-        for i in 0..self.board_size {
-            let mut v = self.board[i].clone();
+    /// Returns the number of moves that changed the board so far
+    pub fn moves(&self) -> usize {
+        self.moves
+    }
 
-            self.vec_compress(&mut v);
+    /// Returns the seed the game was constructed with, if any
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
 
-            self.board[i] = v;
-        }
+    /// Limits the number of moves that can be undone for the rest of the game
+    pub fn limit_undos(&mut self, budget: usize) {
+        self.undos_left = Some(budget);
+    }
+
+    /// Returns the number of undos left, `None` if undos are unlimited
+    pub fn undos_left(&self) -> Option<usize> {
+        self.undos_left
+    }
+
+    /// Ends the game once it has lasted this many moves in total, unless it was won by then
+    pub fn limit_moves(&mut self, limit: usize) {
+        self.move_limit = Some(limit);
     }
 
-    /// Performs the compression of board's values towards the right most column
-    fn move_right(&mut self) {
-        for i in 0..self.board_size {
-            let mut v = self.board[i].clone();
+    /// Returns the limit on the number of moves, `None` if there is none
+    pub fn move_limit(&self) -> Option<usize> {
+        self.move_limit
+    }
 
-            v.reverse();
-            self.vec_compress(&mut v);
-            v.reverse();
+    /// Returns the number of moves left to play, `None` if moves are unlimited
+    pub fn moves_left(&self) -> Option<usize> {
+        self.move_limit
+            .map(|limit| limit.saturating_sub(self.moves))
+    }
 
-            self.board[i] = v;
+    fn snapshot(&self) -> Snapshot<R> {
+        Snapshot {
+            board: self.board.clone(),
+            continued: self.continued,
+            score: self.score,
+            moves: self.moves,
+            spawned: self.spawned,
+            rng: self.rng.clone(),
         }
     }
 
-    /// Performs the compression of board's values towards the top row
-    fn move_up(&mut self) {
-        for i in 0..self.board_size {
-            let mut v = (0..self.board_size).map(|j| self.board[j][i]).collect();
+    fn restore(&mut self, snapshot: Snapshot<R>) {
+        self.board = snapshot.board;
+        self.continued = snapshot.continued;
+        self.score = snapshot.score;
+        self.moves = snapshot.moves;
+        self.spawned = snapshot.spawned;
+        self.rng = snapshot.rng;
+        self.spawner.rewind(self.spawned);
+    }
 
-            self.vec_compress(&mut v);
+    /// Takes back the last move, along with the tile it spawned.
+    /// Output bool is false if there was nothing to undo or the undo budget is spent
+    pub fn undo(&mut self) -> bool {
+        if self.undos_left == Some(0) {
+            return false;
+        }
+        let Some(previous) = self.undo_stack.pop_back() else {
+            return false;
+        };
 
-            for (j, tile) in v.iter().enumerate().take(self.board_size) {
-                self.board[j][i] = *tile;
-            }
+        self.redo_stack.push(self.snapshot());
+        self.restore(previous);
+        if let Some(left) = self.undos_left.as_mut() {
+            *left -= 1;
         }
+
+        true
     }
 
-    /// Performs the compression of board's values towards the bottom row
-    fn move_down(&mut self) {
-        for i in 0..self.board_size {
-            let mut v: Vec<Tile> = (0..self.board_size).map(|j| self.board[j][i]).collect();
+    /// Replays the last undone move, output bool is false if there was nothing to redo.
+    /// The undo it replays is given back to the budget, so undoing it again costs nothing extra
+    pub fn redo(&mut self) -> bool {
+        let Some(next) = self.redo_stack.pop() else {
+            return false;
+        };
+
+        self.undo_stack.push_back(self.snapshot());
+        self.restore(next);
+        if let Some(left) = self.undos_left.as_mut() {
+            *left += 1;
+        }
 
-            v.reverse();
-            self.vec_compress(&mut v);
-            v.reverse();
+        true
+    }
 
-            for (j, tile) in v.iter().enumerate().take(self.board_size) {
-                self.board[j][i] = *tile;
-            }
+    fn spawn_start_tiles(&mut self) {
+        for _ in 0..self.rules.start_tiles() {
+            self.spawn();
         }
     }
 
-    /// Sets a random empty cell to 2 (90%) or 4 (10%). No-op if board is full.
-    fn spawn(&mut self) {
-        let empty: Vec<(usize, usize)> = (0..self.board_size)
-            .flat_map(|r| (0..self.board_size).map(move |c| (r, c)))
-            .filter(|&(r, c)| self.board[r][c] == Tile::Empty)
-            .collect();
-        if empty.is_empty() {
-            return;
+    /// Spawns as many tiles as the rules call for after a move, or until the board is full
+    fn spawn_after_move(&mut self) -> Vec<Spawn> {
+        (0..self.rules.count())
+            .map_while(|_| self.spawn())
+            .collect()
+    }
+
+    /// Sets an empty cell picked by the [`Spawner`] to a tile allowed by the [`SpawnRules`].
+    /// No-op if board is full.
+    fn spawn(&mut self) -> Option<Spawn> {
+        let spawn = self
+            .spawner
+            .spawn(&self.board, &self.rules, &mut self.rng)?;
+        self.board[spawn.at.0][spawn.at.1] = spawn.tile;
+        self.spawned += 1;
+
+        Some(spawn)
+    }
+
+    /// Places a chosen tile instead of a spawned one, as part of the last move.
+    /// Output bool is false if the cell isn't empty or the [`SpawnRules`] don't allow the tile
+    pub fn place(&mut self, spawn: Spawn) -> bool {
+        let (r, c) = spawn.at;
+        if self.board.get(r).and_then(|row| row.get(c)) != Some(&Tile::EMPTY)
+            || self.rules.probability(spawn.tile) == 0.0
+        {
+            return false;
         }
-        let mut rng = rand::rng();
-        let (r, c) = empty[rng.random_range(0..empty.len())];
-        self.board[r][c] = if rng.random_bool(0.1) {
-            Tile::Four
-        } else {
-            Tile::Two
-        };
+        self.board[r][c] = spawn.tile;
+
+        true
     }
 
     /// Returns the current largest tile on the board
@@ -220,27 +524,30 @@ impl Game {
             .iter()
             .flatten()
             .copied()
+            .filter(|tile| !tile.is_blocked())
             .max()
-            .unwrap_or(Tile::Empty)
+            .unwrap_or(Tile::EMPTY)
     }
 
-    /// Refreshes(spawns new tile on an empty cell) the board after a valid move
+    /// Refreshes(spawns new tiles on empty cells) the board after a valid move
     pub fn refresh(&mut self) {
-        self.spawn();
+        self.spawn_after_move();
     }
 
     /// Verify if board is filled and no valid moves left
     fn is_locked(&self) -> bool {
-        if self.contains(Tile::Empty) {
+        if self.contains(Tile::EMPTY) {
             return false;
         }
 
-        for i in 0..self.board_size {
-            for j in 0..self.board_size {
-                if i != self.board_size - 1 && self.board[i][j] == self.board[i + 1][j] {
+        let Size { width, height } = self.size;
+        let merge = |a: Tile, b: Tile| a == b && !a.is_blocked();
+        for i in 0..height {
+            for j in 0..width {
+                if i != height - 1 && merge(self.board[i][j], self.board[i + 1][j]) {
                     return false;
                 }
-                if j != self.board_size - 1 && self.board[i][j] == self.board[i][j + 1] {
+                if j != width - 1 && merge(self.board[i][j], self.board[i][j + 1]) {
                     return false;
                 }
             }
@@ -254,110 +561,634 @@ impl Game {
         self.board.iter().any(|v| v.contains(&x))
     }
 
+    /// Check if the winning tile has been reached, or the goal met if the game has one
+    pub fn has_won(&self) -> bool {
+        match self.goal {
+            Some(goal) => goal.is_met(&self.board, self.score),
+            None => self.largest_tile() >= self.winning,
+        }
+    }
+
+    /// Continues the game after it was won, it goes on until no moves are left
+    pub fn keep_playing(&mut self) {
+        self.continued = true;
+    }
+
     pub fn status(&self) -> Status {
-        if self.contains(self.winning) {
+        let won = self.has_won();
+        if won && !self.continued {
             Status::Won
+        } else if self.moves_left() == Some(0) {
+            Status::Lost(LossReason::OutOfMoves)
         } else if self.is_locked() {
-            Status::Lost
+            Status::Lost(LossReason::Locked)
+        } else if won {
+            Status::WonContinuing
         } else {
             Status::On
         }
     }
 
     /// [`Game`] API entry-point, operated by [`Move`] as input
-    /// Output bool is used to check if move caused any change to the board
+    /// Output bool is used to check if move caused any change to the board,
+    /// it is false without a change once the move limit is reached
     pub fn mover(&mut self, mov: Move) -> bool {
-        let temp = self.board.clone();
-
-        match mov {
-            Move::Left => self.move_left(),
-            Move::Right => self.move_right(),
-            Move::Up => self.move_up(),
-            Move::Down => self.move_down(),
-            _ => (),
+        if self.moves_left() == Some(0) {
+            return false;
+        }
+        let before = self.snapshot();
+
+        self.score += slide(&mut self.board, mov);
+
+        if self.board == before.board {
+            return false;
         }
+        self.record(before);
 
-        self.board != temp
+        true
     }
 
-    /// Compress a row/column, keeps track of score earned from merges
-    fn vec_compress(&mut self, v: &mut Vec<Tile>) {
-        v.retain(|x| *x != Tile::Empty);
-        let vl = v.len();
+    /// Plays the move and spawns the next tile, like [`Game::mover`] followed by
+    /// [`Game::refresh`], describing where every tile went. `None` if no tile moved.
+    pub fn play(&mut self, mov: Move) -> Option<MoveOutcome> {
+        if self.moves_left() == Some(0) {
+            return None;
+        }
+        let before = self.snapshot();
 
-        if vl > 1 {
-            for i in 0..vl - 1 {
-                if v[i] == v[i + 1] {
-                    let promoted = v[i].promote();
-                    v[i] = promoted;
-                    v[i + 1] = Tile::Empty;
-                    self.score += promoted.score();
-                }
+        let mut tiles: Vec<TileMove> = vec![];
+        let mut merges = vec![];
+        let score = slide_traced(&mut self.board, mov, |from, to, merged| {
+            let tile = before.board[from.0][from.1];
+            if merged {
+                let other = tiles
+                    .iter()
+                    .rev()
+                    .find(|tile_move| tile_move.to == to)
+                    .expect("a merge follows the tile merged into");
+                merges.push(Merge {
+                    from: [other.from, from],
+                    at: to,
+                    tile: tile.promote(),
+                });
             }
+            tiles.push(TileMove { from, to, tile });
+        });
+
+        if self.board == before.board {
+            return None;
+        }
+        self.score += score;
+        self.record(before);
+
+        Some(MoveOutcome {
+            mov,
+            tiles,
+            merges,
+            score,
+            spawns: self.spawn_after_move(),
+        })
+    }
+
+    /// Keeps the state from before a move that changed the board, to undo it later
+    fn record(&mut self, before: Snapshot<R>) {
+        if self.undo_stack.len() == HISTORY_LIMIT {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(before);
+        self.redo_stack.clear();
+        self.moves += 1;
+    }
+}
+
+/// Moves every tile on the board as far as it goes in the direction of the [`Move`],
+/// merging equal neighbours once per move. Returns the score earned from merges.
+pub fn slide(board: &mut Board, mov: Move) -> usize {
+    slide_traced(board, mov, |_, _, _| {})
+}
+
+/// Returns the moves that change the board
+pub fn legal_moves(board: &Board) -> impl Iterator<Item = Move> + '_ {
+    Move::ALL.into_iter().filter(move |&mov| {
+        let mut next = board.clone();
+        slide(&mut next, mov);
+        &next != board
+    })
+}
+
+/// Same as [`slide`], reporting the source and destination of every tile as it goes,
+/// along with whether it merged into the tile already at the destination
+fn slide_traced(board: &mut Board, mov: Move, mut on_tile: impl FnMut(Cell, Cell, bool)) -> usize {
+    let height = board.len();
+    let width = board.first().map_or(0, Vec::len);
+
+    let mut score = 0;
+    for cells in lines(width, height, mov) {
+        let mut line: Vec<Tile> = cells.iter().map(|&(r, c)| board[r][c]).collect();
+
+        score += compress(&mut line, |from, to, merged| {
+            on_tile(cells[from], cells[to], merged)
+        });
+
+        for (&(r, c), tile) in cells.iter().zip(line) {
+            board[r][c] = tile;
         }
+    }
+
+    score
+}
 
-        v.retain(|x| *x != Tile::Empty);
-        v.resize(self.board_size, Tile::Empty);
+/// Rows or columns compressed by the move, each starting from the edge tiles move towards
+fn lines(width: usize, height: usize, mov: Move) -> Vec<Vec<Cell>> {
+    match mov {
+        Move::Left => (0..height)
+            .map(|r| (0..width).map(|c| (r, c)).collect())
+            .collect(),
+        Move::Right => (0..height)
+            .map(|r| (0..width).rev().map(|c| (r, c)).collect())
+            .collect(),
+        Move::Up => (0..width)
+            .map(|c| (0..height).map(|r| (r, c)).collect())
+            .collect(),
+        Move::Down => (0..width)
+            .map(|c| (0..height).rev().map(|r| (r, c)).collect())
+            .collect(),
+        Move::Dont => vec![],
     }
 }
 
+/// Compress a row/column towards its start, returns the score earned from merges.
+/// Every tile is reported with its old and new index, and whether it merged there.
+fn compress(v: &mut [Tile], mut on_tile: impl FnMut(usize, usize, bool)) -> usize {
+    let mut score = 0;
+    let mut end = 0;
+    // The last placed tile can take one merge, a merged tile can't merge again
+    let mut mergeable = false;
+
+    for i in 0..v.len() {
+        let tile = std::mem::take(&mut v[i]);
+        if tile == Tile::EMPTY {
+            continue;
+        }
+        // Nothing gets past a blocked cell, the tiles after it pile up against it
+        if tile.is_blocked() {
+            v[i] = tile;
+            end = i + 1;
+            mergeable = false;
+            on_tile(i, i, false);
+            continue;
+        }
+
+        if mergeable && v[end - 1] == tile {
+            let promoted = tile.promote();
+            v[end - 1] = promoted;
+            score += promoted.score();
+            mergeable = false;
+            on_tile(i, end - 1, true);
+        } else {
+            v[end] = tile;
+            end += 1;
+            mergeable = true;
+            on_tile(i, end - 1, false);
+        }
+    }
+
+    score
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn vec_compress_no_merge() {
-        let mut v = vec![Tile::Two, Tile::Four, Tile::Eight, Tile::Empty];
-        let mut game = Game::new(4, Tile::Four);
-        game.vec_compress(&mut v);
-        assert_eq!(v, vec![Tile::Two, Tile::Four, Tile::Eight, Tile::Empty]);
+    fn compress_no_merge() {
+        let mut v = vec![Tile::TWO, Tile::FOUR, Tile::EIGHT, Tile::EMPTY];
+        compress(&mut v, |_, _, _| {});
+        assert_eq!(v, vec![Tile::TWO, Tile::FOUR, Tile::EIGHT, Tile::EMPTY]);
     }
 
     #[test]
-    fn vec_compress_single_merge() {
-        let mut v = vec![Tile::Two, Tile::Two, Tile::Empty, Tile::Empty];
-        let mut game = Game::new(4, Tile::Four);
-        game.vec_compress(&mut v);
-        assert_eq!(v, vec![Tile::Four, Tile::Empty, Tile::Empty, Tile::Empty]);
+    fn compress_single_merge() {
+        let mut v = vec![Tile::TWO, Tile::TWO, Tile::EMPTY, Tile::EMPTY];
+        compress(&mut v, |_, _, _| {});
+        assert_eq!(v, vec![Tile::FOUR, Tile::EMPTY, Tile::EMPTY, Tile::EMPTY]);
     }
 
     #[test]
-    fn vec_compress_multiple_merges() {
-        let mut v = vec![Tile::Four, Tile::Four, Tile::Four, Tile::Four];
-        let mut game = Game::new(4, Tile::Four);
-        game.vec_compress(&mut v);
-        assert_eq!(v, vec![Tile::Eight, Tile::Eight, Tile::Empty, Tile::Empty]);
+    fn compress_multiple_merges() {
+        let mut v = vec![Tile::FOUR, Tile::FOUR, Tile::FOUR, Tile::FOUR];
+        compress(&mut v, |_, _, _| {});
+        assert_eq!(v, vec![Tile::EIGHT, Tile::EIGHT, Tile::EMPTY, Tile::EMPTY]);
     }
 
     #[test]
-    fn vec_compress_no_double_merge() {
+    fn compress_no_double_merge() {
         // [2,2,2,0] → [4,2,0,0]: only first pair merges, score=4
-        let mut v = vec![Tile::Two, Tile::Two, Tile::Two, Tile::Empty];
-        let mut game = Game::new(4, Tile::Four);
-        game.vec_compress(&mut v);
-        assert_eq!(v, vec![Tile::Four, Tile::Two, Tile::Empty, Tile::Empty]);
+        let mut v = vec![Tile::TWO, Tile::TWO, Tile::TWO, Tile::EMPTY];
+        assert_eq!(compress(&mut v, |_, _, _| {}), 4);
+        assert_eq!(v, vec![Tile::FOUR, Tile::TWO, Tile::EMPTY, Tile::EMPTY]);
+    }
+
+    #[test]
+    fn compress_stops_at_blocked_cells() {
+        let mut v = vec![
+            Tile::EMPTY,
+            Tile::BLOCKED,
+            Tile::TWO,
+            Tile::EMPTY,
+            Tile::TWO,
+        ];
+        let mut moved = vec![];
+        assert_eq!(compress(&mut v, |from, to, _| moved.push((from, to))), 4);
+        assert_eq!(
+            v,
+            vec![
+                Tile::EMPTY,
+                Tile::BLOCKED,
+                Tile::FOUR,
+                Tile::EMPTY,
+                Tile::EMPTY
+            ]
+        );
+        assert_eq!(moved, vec![(1, 1), (2, 2), (4, 2)]);
+    }
+
+    #[test]
+    fn compress_past_4096() {
+        let mut v = vec![
+            Tile::FOUR_THOUSAND_NINETY_SIX,
+            Tile::FOUR_THOUSAND_NINETY_SIX,
+            Tile::EMPTY,
+        ];
+        assert_eq!(compress(&mut v, |_, _, _| {}), 8192);
+        assert_eq!(v[0].score(), 8192);
+        assert_eq!(v[0].to_string(), "8192");
+    }
+
+    #[test]
+    fn tiles_by_exponent() {
+        let tile = Tile::from_exponent(17).unwrap();
+        assert_eq!(tile.score(), 131072);
+        assert_eq!(Tile::from_score(131072), Some(tile));
+        assert!(tile > Tile::FOUR_THOUSAND_NINETY_SIX);
+        assert_eq!(Tile::from_score(0), Some(Tile::EMPTY));
+        assert_eq!(Tile::from_score(1), None);
+        assert_eq!(Tile::from_score(96), None);
+        assert_eq!(Tile::from_exponent(Tile::MAX_EXPONENT + 1), None);
     }
 
     #[test]
     fn score_starts_at_zero() {
-        let game = Game::new(4, Tile::TwoThousandFourtyEight);
+        let game = Game::new(4, Tile::TWO_THOUSAND_FORTY_EIGHT);
         assert_eq!(game.score(), 0);
     }
 
     #[test]
     fn score_accumulates_after_merge() {
-        let mut game = Game::new(2, Tile::TwoThousandFourtyEight);
+        let mut game = Game::new(2, Tile::TWO_THOUSAND_FORTY_EIGHT);
         // Force a known board state: [2,2] / [0,0]
-        game.board[0] = vec![Tile::Two, Tile::Two];
-        game.board[1] = vec![Tile::Empty, Tile::Empty];
+        game.board[0] = vec![Tile::TWO, Tile::TWO];
+        game.board[1] = vec![Tile::EMPTY, Tile::EMPTY];
         game.mover(Move::Left);
         assert_eq!(game.score(), 4);
     }
 
+    #[test]
+    fn play_describes_the_move() {
+        let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 3);
+        game.board = vec![vec![Tile::EMPTY; 4]; 4];
+        game.board[1] = vec![Tile::TWO, Tile::EMPTY, Tile::TWO, Tile::FOUR];
+        game.board[3][0] = Tile::EIGHT;
+
+        let outcome = game.play(Move::Right).unwrap();
+        assert_eq!(outcome.score, 4);
+        assert_eq!(
+            outcome.tiles,
+            vec![
+                TileMove {
+                    from: (1, 3),
+                    to: (1, 3),
+                    tile: Tile::FOUR
+                },
+                TileMove {
+                    from: (1, 2),
+                    to: (1, 2),
+                    tile: Tile::TWO
+                },
+                TileMove {
+                    from: (1, 0),
+                    to: (1, 2),
+                    tile: Tile::TWO
+                },
+                TileMove {
+                    from: (3, 0),
+                    to: (3, 3),
+                    tile: Tile::EIGHT
+                },
+            ]
+        );
+        assert_eq!(
+            outcome.merges,
+            vec![Merge {
+                from: [(1, 2), (1, 0)],
+                at: (1, 2),
+                tile: Tile::FOUR
+            }]
+        );
+        let [spawn] = outcome.spawns[..] else {
+            panic!("one tile spawns per move");
+        };
+        assert_eq!(game.board()[spawn.at.0][spawn.at.1], spawn.tile);
+        assert_eq!(game.score(), 4);
+        assert_eq!(game.moves(), 1);
+
+        game.board = vec![vec![Tile::TWO, Tile::FOUR, Tile::TWO, Tile::FOUR]; 4];
+        assert!(game.play(Move::Left).is_none());
+    }
+
+    #[test]
+    fn play_matches_mover_and_refresh() {
+        let mut played = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 8);
+        let mut moved = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 8);
+        for mov in [Move::Left, Move::Up, Move::Right, Move::Down].repeat(16) {
+            let outcome = played.play(mov);
+            assert_eq!(outcome.is_some(), moved.mover(mov));
+            if outcome.is_some() {
+                moved.refresh();
+            }
+            assert_eq!(played.board(), moved.board());
+            assert_eq!(played.score(), moved.score());
+        }
+    }
+
+    #[test]
+    fn spawn_rules_decide_tiles() {
+        let count = |game: &Game| {
+            game.board()
+                .iter()
+                .flatten()
+                .filter(|t| **t != Tile::EMPTY)
+                .count()
+        };
+        assert_eq!(
+            count(&Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 1)),
+            2
+        );
+
+        let rules = SpawnRules::new(vec![(Tile::EIGHT, 1.0)], 3, 5).unwrap();
+        let mut game = Game::with_rules(4, Tile::TWO_THOUSAND_FORTY_EIGHT, rules, 1);
+        assert_eq!(count(&game), 5);
+        assert!(
+            game.board()
+                .iter()
+                .flatten()
+                .all(|t| [Tile::EMPTY, Tile::EIGHT].contains(t))
+        );
+
+        let outcome = Move::ALL
+            .into_iter()
+            .find_map(|mov| game.play(mov))
+            .unwrap();
+        assert_eq!(outcome.spawns.len(), 3);
+        assert!(outcome.spawns.iter().all(|spawn| spawn.tile == Tile::EIGHT));
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        let moves = [Move::Left, Move::Up, Move::Right, Move::Down].repeat(8);
+        let mut a = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 42);
+        let mut b = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 42);
+        assert_eq!(a.board(), b.board());
+
+        for mov in moves {
+            if a.mover(mov) {
+                a.refresh();
+            }
+            if b.mover(mov) {
+                b.refresh();
+            }
+            assert_eq!(a.board(), b.board());
+        }
+        assert_eq!(a.score(), b.score());
+        assert_eq!(a.seed(), Some(42));
+    }
+
+    #[test]
+    fn injected_rng_has_no_seed() {
+        let game = Game::with_rng(4, Tile::TWO_THOUSAND_FORTY_EIGHT, GameRng::seed_from_u64(7));
+        assert_eq!(game.seed(), None);
+        assert_eq!(
+            game.board(),
+            Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 7).board()
+        );
+    }
+
+    #[test]
+    fn undo_restores_board_score_and_spawns() {
+        let mut game = Game::with_seed(2, Tile::TWO_THOUSAND_FORTY_EIGHT, 3);
+        game.board[0] = vec![Tile::TWO, Tile::TWO];
+        game.board[1] = vec![Tile::EMPTY, Tile::EMPTY];
+        let start = game.board.clone();
+
+        assert!(game.mover(Move::Left));
+        game.refresh();
+        let after = game.board.clone();
+
+        assert!(game.undo());
+        assert_eq!(game.board(), &start);
+        assert_eq!(game.score(), 0);
+
+        // Replaying the same move spawns the same tile
+        assert!(game.mover(Move::Left));
+        game.refresh();
+        assert_eq!(game.board(), &after);
+        assert_eq!(game.score(), 4);
+    }
+
+    #[test]
+    fn redo_replays_undone_move() {
+        let mut game = Game::with_seed(2, Tile::TWO_THOUSAND_FORTY_EIGHT, 3);
+        game.board[0] = vec![Tile::TWO, Tile::TWO];
+        game.board[1] = vec![Tile::EMPTY, Tile::EMPTY];
+        assert!(!game.redo());
+
+        assert!(game.mover(Move::Left));
+        game.refresh();
+        let after = game.board.clone();
+
+        assert!(game.undo());
+        assert!(game.redo());
+        assert_eq!(game.board(), &after);
+        assert_eq!(game.score(), 4);
+        assert!(!game.redo());
+    }
+
+    #[test]
+    fn placed_tiles_follow_the_rules() {
+        let mut game = Game::with_seed(2, Tile::TWO_THOUSAND_FORTY_EIGHT, 1);
+        game.board = vec![vec![Tile::TWO, Tile::EMPTY], vec![Tile::EMPTY; 2]];
+        let spawn = |at, tile| Spawn { at, tile };
+        assert!(!game.place(spawn((0, 0), Tile::TWO)));
+        assert!(!game.place(spawn((2, 0), Tile::TWO)));
+        assert!(!game.place(spawn((0, 1), Tile::EIGHT)));
+        assert!(game.place(spawn((0, 1), Tile::FOUR)));
+        assert_eq!(game.board()[0], vec![Tile::TWO, Tile::FOUR]);
+    }
+
+    #[test]
+    fn undo_budget_is_enforced() {
+        let mut game = Game::with_seed(2, Tile::TWO_THOUSAND_FORTY_EIGHT, 3);
+        game.board[0] = vec![Tile::TWO, Tile::EMPTY];
+        game.board[1] = vec![Tile::EMPTY, Tile::EMPTY];
+        game.limit_undos(1);
+        assert!(!game.undo());
+
+        assert!(game.mover(Move::Right));
+        assert!(game.undo());
+        assert_eq!(game.undos_left(), Some(0));
+
+        assert!(game.mover(Move::Right));
+        assert!(!game.undo());
+    }
+
+    #[test]
+    fn redone_undos_are_given_back() {
+        let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 3);
+        game.limit_undos(1);
+        let mov = legal_moves(game.board()).next().unwrap();
+        assert!(game.mover(mov));
+        game.refresh();
+
+        for _ in 0..3 {
+            assert!(game.undo());
+            assert_eq!(game.undos_left(), Some(0));
+            assert!(game.redo());
+            assert_eq!(game.undos_left(), Some(1));
+        }
+        assert!(game.undo());
+        assert!(!game.undo());
+    }
+
+    #[test]
+    fn undoing_the_winning_move_takes_back_continuing() {
+        let mut game = Game::with_seed(2, Tile::EIGHT, 1);
+        game.board = vec![vec![Tile::FOUR, Tile::FOUR], vec![Tile::EMPTY, Tile::EMPTY]];
+        assert!(game.mover(Move::Left));
+        game.keep_playing();
+        assert_eq!(game.status(), Status::WonContinuing);
+
+        assert!(game.undo());
+        assert_eq!(game.status(), Status::On);
+        assert!(game.mover(Move::Left));
+        assert_eq!(game.status(), Status::Won);
+
+        game.keep_playing();
+        assert!(game.undo());
+        assert!(game.redo());
+        assert_eq!(game.status(), Status::WonContinuing);
+    }
+
+    #[test]
+    fn rectangular_board_moves() {
+        let mut game = Game::with_seed((3, 5), Tile::TWO_THOUSAND_FORTY_EIGHT, 1);
+        assert_eq!(game.size(), Size::from((3, 5)));
+        assert_eq!(game.board().len(), 5);
+        assert!(game.board().iter().all(|row| row.len() == 3));
+
+        game.board = vec![vec![Tile::EMPTY; 3]; 5];
+        game.board[0][2] = Tile::TWO;
+        game.board[4][2] = Tile::TWO;
+        assert!(game.mover(Move::Down));
+        assert_eq!(game.board()[4][2], Tile::FOUR);
+        assert!(game.mover(Move::Left));
+        assert_eq!(game.board()[4][0], Tile::FOUR);
+        game.refresh();
+        assert_eq!(
+            game.board()
+                .iter()
+                .flatten()
+                .filter(|&&t| t != Tile::EMPTY)
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn rectangular_board_locks() {
+        let mut game = Game::with_seed((2, 3), Tile::TWO_THOUSAND_FORTY_EIGHT, 1);
+        game.board = vec![
+            vec![Tile::TWO, Tile::FOUR],
+            vec![Tile::FOUR, Tile::TWO],
+            vec![Tile::TWO, Tile::FOUR],
+        ];
+        assert!(matches!(game.status(), Status::Lost(LossReason::Locked)));
+        game.board[2][1] = Tile::TWO;
+        assert!(matches!(game.status(), Status::On));
+    }
+
+    #[test]
+    fn keep_playing_after_winning() {
+        let mut game = Game::with_seed(2, Tile::EIGHT, 1);
+        game.board = vec![vec![Tile::FOUR, Tile::FOUR], vec![Tile::EMPTY, Tile::EMPTY]];
+        assert!(matches!(game.status(), Status::On));
+
+        assert!(game.mover(Move::Left));
+        assert!(matches!(game.status(), Status::Won));
+
+        game.keep_playing();
+        assert!(matches!(game.status(), Status::WonContinuing));
+
+        game.board = vec![vec![Tile::TWO, Tile::SIXTEEN], vec![Tile::FOUR, Tile::TWO]];
+        assert!(matches!(game.status(), Status::Lost(LossReason::Locked)));
+        assert!(game.has_won());
+    }
+
+    #[test]
+    fn blocked_cells_never_merge() {
+        let board = vec![
+            vec![Tile::BLOCKED, Tile::BLOCKED],
+            vec![Tile::TWO, Tile::FOUR],
+        ];
+        let game = Game::from_board(board, Tile::EIGHT, 0).unwrap();
+        assert_eq!(game.largest_tile(), Tile::FOUR);
+        assert_eq!(game.status(), Status::Lost(LossReason::Locked));
+
+        assert!(
+            Game::from_board(vec![vec![Tile::TWO; 2], vec![Tile::TWO]], Tile::EIGHT, 0).is_err()
+        );
+        assert!(Game::from_board(vec![vec![Tile::TWO; 2]], Tile::EIGHT, 0).is_err());
+    }
+
+    #[test]
+    fn move_limit_ends_the_game() {
+        let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 6);
+        game.limit_moves(2);
+        assert_eq!(game.moves_left(), Some(2));
+        let mut played = 0;
+        for mov in [Move::Left, Move::Right].repeat(4) {
+            if game.mover(mov) {
+                game.refresh();
+                played += 1;
+            }
+        }
+        assert_eq!(played, 2);
+        assert_eq!(game.moves_left(), Some(0));
+        assert_eq!(game.status(), Status::Lost(LossReason::OutOfMoves));
+        assert!(game.play(Move::Up).is_none());
+
+        assert!(game.undo());
+        assert_eq!(game.status(), Status::On);
+
+        // Reaching the winning tile on the last move still wins
+        game.board[0][0] = Tile::TWO_THOUSAND_FORTY_EIGHT;
+        assert!(game.play(Move::Down).is_some() || game.play(Move::Up).is_some());
+        assert_eq!(game.status(), Status::Won);
+    }
+
     #[test]
     fn current_largest_tile_on_board() {
-        let mut game = Game::new(4, Tile::TwoThousandFourtyEight);
-        game.board[0][0] = Tile::SixtyFour;
-        assert_eq!(game.largest_tile(), Tile::SixtyFour);
+        let mut game = Game::new(4, Tile::TWO_THOUSAND_FORTY_EIGHT);
+        game.board[0][0] = Tile::SIXTY_FOUR;
+        assert_eq!(game.largest_tile(), Tile::SIXTY_FOUR);
     }
 }
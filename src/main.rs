@@ -1,15 +1,52 @@
-use std::{error::Error, time::Duration};
+use std::{
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+    process,
+    time::{Duration, Instant},
+};
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum, builder::PossibleValuesParser};
 use crossterm::event::{self, Event, KeyCode, KeyModifiers};
-use tui::{MoveEffects, TermGuard};
-use twozero48::{Game, Move, Status, Tile};
+use tui::{Hud, MoveEffects, TermGuard};
+use twozero48::{
+    Game, LossReason, Move, Size, Status, Tile,
+    engine::{self, GameOptions, Session},
+    puzzle::{self, Puzzle},
+    record::Recording,
+    solver::Expectimax,
+    spawn::{self, SpawnRules},
+};
 
-use crate::milestones::MilestoneChecker;
+use crate::{
+    autoplay::Autoplay,
+    countdown::Countdown,
+    daily::{Attempt, Attempts},
+    hotseat::{Placement, PlayerTwo},
+    milestones::MilestoneChecker,
+    scores::{Category, Entry, HighScores},
+    server::ServeOpts,
+    simulate::SimulateOpts,
+    tournament::TournamentOpts,
+};
 
+mod autoplay;
+mod countdown;
+mod daily;
+mod hotseat;
+mod levels;
 mod milestones;
+mod replay;
+mod scores;
+mod server;
+mod simulate;
+mod storage;
+mod tournament;
 mod tui;
 
+/// Time the solver gets to pick a move, so the UI doesn't freeze
+const SEARCH_BUDGET: Duration = Duration::from_millis(150);
+
 /// Define the arguments and the CLI option interface for twozero48.
 #[derive(Parser)]
 #[clap(
@@ -18,119 +55,674 @@ mod tui;
 )]
 struct Opts {
     /// Game board's length & breadth, should be equal to 2 or greater in value,
-    /// else it will be automatically updated to the minimum value.
-    #[clap(short, long, default_value = "4")]
+    /// else it will be automatically updated to the minimum value. At most 16.
+    #[clap(short, long, default_value = "4", value_parser = parse_side, global = true)]
     pub board_size: usize,
-    /// Game's winning tile value, only 128, 256, 512, 1024, 2048, and 4096 are supported values.
-    #[clap(short, long, default_value = "2048", value_parser = parse_winning)]
+    /// Game board's width, overrides the board size, from 2 to 16.
+    #[clap(long, value_parser = parse_side, global = true)]
+    pub width: Option<usize>,
+    /// Game board's height, overrides the board size, from 2 to 16.
+    #[clap(long, value_parser = parse_side, global = true)]
+    pub height: Option<usize>,
+    /// Game's winning tile value, any power of two from 128 upwards, e.g. 128, 2048 or 8192.
+    #[clap(short, long, default_value = "2048", value_parser = parse_winning, global = true)]
     pub winning: Tile,
+    /// Seed for spawning tiles, the same seed and moves always reproduce the same game.
+    /// A random seed is picked if not provided, it is shown in the footer to share the game.
+    #[clap(long, global = true)]
+    pub seed: Option<u64>,
+    /// Tiles that spawn with their weights, as value:weight pairs.
+    #[clap(long, default_value = "2:0.9,4:0.1", value_parser = parse_spawn_weights, global = true)]
+    pub spawn: SpawnWeights,
+    /// Number of tiles spawned after every move.
+    #[clap(long, default_value = "1", global = true)]
+    pub spawn_count: usize,
+    /// Number of tiles on the board when the game starts.
+    #[clap(long, default_value = "2", global = true)]
+    pub start_tiles: usize,
+    /// Where tiles spawn: at random, where they hurt the most (evil) or help the most (friendly).
+    #[clap(long, default_value = "random", value_parser = PossibleValuesParser::new(spawn::BUILTIN), global = true)]
+    pub spawner: String,
+    /// Number of moves that can be undone over the whole game, unlimited if not provided.
+    #[clap(long)]
+    pub undos: Option<usize>,
+    /// How the game is played. Hot-seat and time-attack games aren't saved,
+    /// hot-seat games aren't ranked either.
+    #[clap(long, value_enum, default_value = "classic")]
+    pub mode: Mode,
+    /// Number of moves to reach the target tile in within moves mode.
+    #[clap(long, default_value = "300", value_parser = clap::value_parser!(u64).range(1..))]
+    pub limit: u64,
+    /// Tile to reach in moves mode, the winning tile if not provided.
+    #[clap(long, value_parser = parse_winning)]
+    pub target: Option<Tile>,
+    /// Seconds on the clock in time-attack mode.
+    #[clap(long, default_value = "120", value_parser = clap::value_parser!(u64).range(1..))]
+    pub time: u64,
+    /// Continue the game that was saved when quitting last time, with the seed, mode and
+    /// undos it was started with.
+    #[clap(long, conflicts_with_all = ["seed", "mode", "limit", "target", "time", "undos"])]
+    pub resume: bool,
+    /// Let the solver play the game, Space pauses it and a direction key takes over.
+    /// Games played by the solver don't make it to the high scores.
+    #[clap(long)]
+    pub autoplay: bool,
+    /// Milliseconds between two moves of the solver, adjusted with + and - while playing.
+    #[clap(long, default_value = "200", requires = "autoplay")]
+    pub speed: u64,
+    /// Show moves right away, without tiles sliding across the board, for slow terminals.
+    #[clap(long)]
+    pub no_animation: bool,
+    /// Record the moves to this file as the game is played, to watch it again with `replay`.
+    #[clap(long)]
+    pub record: Option<PathBuf>,
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    /// Play alone until no moves are left
+    Classic,
+    /// Two players at one keyboard, the second places the tile after every move of the first
+    HotSeat,
+    /// Score as much as possible before the clock runs out
+    TimeAttack,
+    /// Reach the target tile within a limited number of moves
+    Moves,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Continue a game from a save file
+    Load {
+        /// Path to the save file
+        file: PathBuf,
+    },
+    /// Play today's challenge, the same standard game for everyone on the same UTC date.
+    /// Only the first attempt of the day counts, undos are disabled.
+    Daily,
+    /// Solve a puzzle, picked from the ones that come with the game unless a file is given
+    Puzzle {
+        /// Path to a puzzle file
+        file: Option<PathBuf>,
+    },
+    /// Watch a recorded game, checking it plays out to the recorded score
+    Replay {
+        /// Path to the recording
+        file: PathBuf,
+    },
+    /// List the high scores of every board size and winning tile
+    Scores,
+    /// Play games with a strategy, without a terminal, and print statistics about them
+    Simulate(SimulateOpts),
+    /// Play games over a line-oriented JSON protocol on stdin and stdout, e.g. from a bot
+    /// written in another language. Send {"cmd": "quit"} or close stdin to stop
+    Engine,
+    /// Host games for bots over TCP, one game per connection with the engine protocol.
+    /// Games end when they are lost, the client quits or takes too long to move
+    Serve(ServeOpts),
+    /// Play every bot on the same seeds and rank them by score, bots are built-in
+    /// strategies or programs speaking the engine protocol
+    Tournament(TournamentOpts),
 }
 
 fn parse_winning(score: &str) -> Result<Tile, String> {
     let score: usize = score
         .parse()
-        .map_err(|_| "Only 128, 256, 512, 1024, 2048, and 4096 are supported values")?;
-    match score {
-        128 => Ok(Tile::OneHundredTwentyEight),
-        256 => Ok(Tile::TwoHundredFiftySix),
-        512 => Ok(Tile::FiveHundredTwelve),
-        1024 => Ok(Tile::OneThousandTwentyFour),
-        2048 => Ok(Tile::TwoThousandFourtyEight),
-        4096 => Ok(Tile::FourHundredNinetySix),
+        .map_err(|_| "Only powers of two from 128 upwards, e.g. 2048, are supported values")?;
+    match Tile::from_score(score) {
+        Some(tile) if tile >= Tile::ONE_HUNDRED_TWENTY_EIGHT => Ok(tile),
         _ => Err(format!(
-            "{score} is not a supported winning value. Only 128, 256, 512, 1024, 2048, and 4096 are supported values."
+            "{score} is not a supported winning value. Only powers of two from 128 upwards, e.g. 2048, are supported values."
         )),
     }
 }
 
+fn parse_side(side: &str) -> Result<usize, String> {
+    let side: usize = side
+        .parse()
+        .map_err(|_| format!("{side} is not a number of cells"))?;
+    if side > engine::MAX_SIDE {
+        return Err(format!(
+            "{side} cells is too many, boards are at most {} cells on a side",
+            engine::MAX_SIDE
+        ));
+    }
+
+    Ok(side)
+}
+
+/// Tiles that spawn with their weights, parsed as a whole from the `--spawn` list
+#[derive(Clone, Debug)]
+struct SpawnWeights(Vec<(Tile, f64)>);
+
+fn parse_spawn_weights(weights: &str) -> Result<SpawnWeights, String> {
+    spawn::parse_weights(weights).map(SpawnWeights)
+}
+
+/// Returns the direction a WASD or arrow key stands for
+fn direction(code: KeyCode) -> Option<Move> {
+    match code {
+        KeyCode::Char('a') | KeyCode::Char('A') | KeyCode::Left => Some(Move::Left),
+        KeyCode::Char('d') | KeyCode::Char('D') | KeyCode::Right => Some(Move::Right),
+        KeyCode::Char('w') | KeyCode::Char('W') | KeyCode::Up => Some(Move::Up),
+        KeyCode::Char('s') | KeyCode::Char('S') | KeyCode::Down => Some(Move::Down),
+        _ => None,
+    }
+}
+
+fn load_or_exit(path: &Path) -> Game {
+    storage::load_game(path).unwrap_or_else(|e| {
+        eprintln!("Couldn't load {}: {e}", path.display());
+        process::exit(1);
+    })
+}
+
+fn load_puzzle_or_exit(path: &Path) -> Puzzle {
+    fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|json| Puzzle::parse(&json))
+        .unwrap_or_else(|e| {
+            eprintln!("Couldn't load {}: {e}", path.display());
+            process::exit(1);
+        })
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     // Collect command line arguments to initiate/configure a game
     let opts = Opts::parse();
-    let mut game = Game::new(opts.board_size, opts.winning);
-    let mut milestone_checker = MilestoneChecker::new(Tile::Empty);
+    let size = Size {
+        width: opts.width.unwrap_or(opts.board_size),
+        height: opts.height.unwrap_or(opts.board_size),
+    };
+    let rules = SpawnRules::new(opts.spawn.0.clone(), opts.spawn_count, opts.start_tiles)
+        .unwrap_or_else(|e| {
+            eprintln!("Invalid spawn rules: {e}");
+            process::exit(1);
+        });
+    if let Some(Command::Simulate(sim)) = &opts.command {
+        let seed = opts.seed.unwrap_or_else(rand::random);
+        match simulate::run(sim, size, opts.winning, &rules, &opts.spawner, seed) {
+            Ok(report) => simulate::print(&report, sim.format)?,
+            Err(e) => {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    let options = GameOptions {
+        size,
+        winning: opts.winning,
+        rules: rules.clone(),
+        spawner: opts.spawner.clone(),
+        seed: opts.seed,
+        move_limit: None,
+    };
+    if let Some(Command::Tournament(tournament)) = &opts.command {
+        match tournament::run(tournament, &options) {
+            Ok((leaderboard, by_bot)) => {
+                if let Some(path) = &tournament.results {
+                    tournament::save_results(&by_bot, path)?;
+                }
+                tournament::print(&leaderboard, tournament.format)?;
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    if let Some(Command::Serve(serve)) = &opts.command {
+        if let Err(e) = server::run(serve, options) {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(Command::Engine) = &opts.command {
+        let mut session = Session::new(options).unwrap_or_else(|e| {
+            eprintln!("{e}");
+            process::exit(1);
+        });
+        engine::serve(&mut session, io::stdin().lock(), io::stdout().lock())?;
+        return Ok(());
+    }
+
+    let scores_path = HighScores::path()?;
+    let mut high_scores = HighScores::load(&scores_path)?;
+    let autosave = storage::autosave_path()?;
+    let today = daily::today();
+    let puzzle = match &opts.command {
+        Some(Command::Puzzle { .. }) if opts.mode != Mode::Classic => {
+            eprintln!("Puzzles are played by their own rules, without a mode");
+            process::exit(1);
+        }
+        Some(Command::Puzzle { file: Some(file) }) => Some(load_puzzle_or_exit(file)),
+        Some(Command::Puzzle { file: None }) => match levels::select(puzzle::builtin())? {
+            Some(puzzle) => Some(puzzle),
+            None => return Ok(()),
+        },
+        _ => None,
+    };
+    let mut game = match &opts.command {
+        Some(Command::Scores) => {
+            scores::print(&high_scores);
+            return Ok(());
+        }
+        Some(Command::Load { file }) => load_or_exit(file),
+        Some(Command::Replay { file }) => {
+            let recording = storage::load_recording(file).unwrap_or_else(|e| {
+                eprintln!("Couldn't load {}: {e}", file.display());
+                process::exit(1);
+            });
+            replay::run(&recording, !opts.no_animation)?;
+            return Ok(());
+        }
+        Some(Command::Daily) => {
+            let path = Attempts::path()?;
+            let mut attempts = Attempts::load(&path)?;
+            if let Some(attempt) = attempts.on(today) {
+                println!("You already played today's challenge, come back tomorrow!");
+                println!("{}", attempt.share_line());
+                return Ok(());
+            }
+            if opts.mode != Mode::Classic || opts.autoplay {
+                eprintln!("The daily challenge is a classic game, played by hand");
+                process::exit(1);
+            }
+            let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, daily::seed(today));
+            game.limit_undos(0);
+            // Starting is what counts, a game that is killed midway stays recorded as quit
+            attempts.record(Attempt::of(today, &game));
+            attempts.store(&path)?;
+            game
+        }
+        Some(Command::Puzzle { .. }) => puzzle.as_ref().expect("the puzzle was loaded").game(),
+        Some(Command::Simulate(_))
+        | Some(Command::Engine)
+        | Some(Command::Serve(_))
+        | Some(Command::Tournament(_)) => {
+            unreachable!("simulations and engines don't open the terminal")
+        }
+        None if opts.resume => {
+            if !autosave.exists() {
+                eprintln!("There is no saved game to resume");
+                process::exit(1);
+            }
+            let game = load_or_exit(&autosave);
+            // The save is consumed, it gets written again on the next quit
+            fs::remove_file(&autosave)?;
+            game
+        }
+        None => {
+            let seed = opts.seed.unwrap_or_else(rand::random);
+            let winning = match opts.mode {
+                Mode::Moves => opts.target.unwrap_or(opts.winning),
+                _ => opts.winning,
+            };
+            let mut game = Game::with_rules(size, winning, rules, seed);
+            if opts.mode == Mode::Moves {
+                game.limit_moves(opts.limit as usize);
+            }
+            game.set_spawner(
+                spawn::builtin(&opts.spawner).expect("spawner names are checked by clap"),
+            );
+            if let Some(budget) = opts.undos {
+                game.limit_undos(budget);
+            }
+            game
+        }
+    };
+    let daily = matches!(opts.command, Some(Command::Daily));
+    // Daily challenges are over after the first attempt and puzzles are started over,
+    // there is nothing to resume
+    let saved = matches!(opts.mode, Mode::Classic | Mode::Moves) && !daily && puzzle.is_none();
+    let hot_seat = opts.mode == Mode::HotSeat;
+    if hot_seat {
+        if opts.autoplay {
+            eprintln!("The solver can't play hot-seat games");
+            process::exit(1);
+        }
+        game.set_spawner(Box::new(PlayerTwo));
+    }
+    let mut recording = opts.record.as_ref().map(|_| {
+        Recording::start(&game).unwrap_or_else(|e| {
+            eprintln!("Can't record this game, {e}");
+            process::exit(1);
+        })
+    });
+    let time_attack = opts.mode == Mode::TimeAttack;
+    let category = if time_attack {
+        // Reaching the winning tile doesn't stop the clock
+        game.keep_playing();
+        Category::of(&game).in_mode(format!("time attack {}s", opts.time))
+    } else {
+        Category::of(&game)
+    };
+    let mut milestone_checker = MilestoneChecker::new(game.largest_tile());
+    let best = high_scores.best(&category);
+    let started = Instant::now();
 
     let mut terminal = TermGuard::new()?;
+    let countdown = time_attack.then(|| Countdown::new(Duration::from_secs(opts.time)));
 
-    let mut valid_move = true;
-    let mut move_effects = MoveEffects::new();
+    let mut solver = Expectimax::default();
+    solver.set_spawn_rules(game.spawn_rules().clone());
+    let mut notice = None;
+    let mut hint = None;
+    let mut autoplay = opts
+        .autoplay
+        .then(|| Autoplay::new(Duration::from_millis(opts.speed)));
+    // Scores only compare games played by hand with the standard spawns
+    let unranked = autoplay.is_some()
+        || *game.spawn_rules() != SpawnRules::default()
+        || game.spawner().name() != "random";
+    let mut move_effects = MoveEffects::new(!opts.no_animation);
+    // Set while player two picks where the next tile goes
+    let mut placing: Option<Placement> = None;
 
-    loop {
+    let end_msg = loop {
+        if countdown.as_ref().is_some_and(Countdown::is_over) {
+            break Some("Time's up!");
+        }
         move_effects.tick();
-        let (xs, ys) = move_effects.shift();
 
-        let message = if !valid_move {
-            Some("No tiles moved — try a different direction")
-        } else {
-            None
-        };
-        terminal.render_board(&game, message, xs, ys, move_effects.flash())?;
+        terminal.render_board(
+            &game,
+            &Hud {
+                best,
+                message: notice,
+                hint,
+                autoplay: autoplay.as_ref(),
+                banner: hot_seat
+                    .then_some(if placing.is_some() {
+                        "PLAYER 2 · PLACE A TILE"
+                    } else {
+                        "PLAYER 1 · MOVE"
+                    })
+                    .or(puzzle.as_ref().map(|puzzle| puzzle.name.as_str())),
+                placement: placing.as_ref().map(Placement::spawn),
+                clock: countdown.as_ref().map(Countdown::remaining),
+            },
+            &move_effects,
+        )?;
 
-        // Non-blocking poll while animating, blocking otherwise
-        if move_effects.is_active() && !event::poll(Duration::from_millis(30))? {
-            continue;
-        }
+        // Non-blocking poll until the next frame, autoplayed move or tick of the clock,
+        // blocking otherwise
+        let timeout = [
+            move_effects.next_frame(),
+            autoplay.as_ref().and_then(Autoplay::wait),
+            countdown.as_ref().map(Countdown::next_tick),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        let has_event = match timeout {
+            Some(timeout) => event::poll(timeout)?,
+            None => true,
+        };
 
-        let event = event::read()?;
-        // Non-key events (resize, focus, mouse) should not preserve the stale
-        // "No tiles moved" message from a previous invalid directional input.
-        if !matches!(event, Event::Key(_)) {
-            valid_move = true;
-            continue;
-        }
-        if let Event::Key(key) = event {
-            let mov = match (key.code, key.modifiers) {
-                (KeyCode::Char('q'), _)
-                | (KeyCode::Char('Q'), _)
-                | (KeyCode::Char('c'), KeyModifiers::CONTROL)
-                | (KeyCode::Esc, _) => break,
-                (KeyCode::Char('a'), _) | (KeyCode::Char('A'), _) | (KeyCode::Left, _) => {
-                    Move::Left
+        let mut placed = false;
+        let mov = if !has_event {
+            match autoplay.as_mut() {
+                Some(autoplay) if autoplay.is_due() => {
+                    autoplay.played();
+                    hint = None;
+                    match solver.decide_within(game.board(), SEARCH_BUDGET.min(autoplay.speed())) {
+                        Some(decision) => decision.mov,
+                        None => continue,
+                    }
                 }
-                (KeyCode::Char('d'), _) | (KeyCode::Char('D'), _) | (KeyCode::Right, _) => {
-                    Move::Right
+                _ => continue,
+            }
+        } else {
+            let event = event::read()?;
+            // Non-key events (resize, focus, mouse) should not preserve the stale
+            // "No tiles moved" message from a previous invalid directional input.
+            let Event::Key(key) = event else {
+                notice = None;
+                continue;
+            };
+            hint = None;
+            if let Some(placement) = &mut placing {
+                match (key.code, key.modifiers) {
+                    (KeyCode::Char('q'), _)
+                    | (KeyCode::Char('Q'), _)
+                    | (KeyCode::Char('c'), KeyModifiers::CONTROL)
+                    | (KeyCode::Esc, _) => break None,
+                    (KeyCode::Char('u'), _) | (KeyCode::Char('U'), _) => {
+                        if game.undo() {
+                            placing = None;
+                            notice = None;
+                        } else if game.undos_left() == Some(0) {
+                            notice = Some("No undos left");
+                        } else {
+                            notice = Some("Nothing to undo");
+                        }
+                        move_effects.clear();
+                        milestone_checker = MilestoneChecker::new(game.largest_tile());
+                    }
+                    (KeyCode::Enter, _) => {
+                        placed = game.place(placement.spawn());
+                        notice = (!placed).then_some("That cell is taken");
+                    }
+                    (KeyCode::Char(' '), _) | (KeyCode::Tab, _) => placement.next_tile(),
+                    (KeyCode::Char(digit), _) if digit.is_ascii_digit() => {
+                        let tile = digit
+                            .to_digit(10)
+                            .and_then(|value| Tile::from_score(value as usize));
+                        notice = (!tile.is_some_and(|tile| placement.choose(tile)))
+                            .then_some("That tile doesn't spawn in this game");
+                    }
+                    (code, _) => {
+                        if let Some(mov) = direction(code) {
+                            placement.move_cursor(mov, game.size());
+                        }
+                    }
                 }
-                (KeyCode::Char('w'), _) | (KeyCode::Char('W'), _) | (KeyCode::Up, _) => Move::Up,
-                (KeyCode::Char('s'), _) | (KeyCode::Char('S'), _) | (KeyCode::Down, _) => {
-                    Move::Down
+                // The turn is over once the tile is placed, the game may be too
+                if !placed {
+                    continue;
                 }
-                _ => Move::Dont,
-            };
-
-            let old_board = game.board().clone();
-            valid_move = game.mover(mov);
+                Move::Dont
+            } else {
+                match (key.code, key.modifiers) {
+                    (KeyCode::Char('q'), _)
+                    | (KeyCode::Char('Q'), _)
+                    | (KeyCode::Char('c'), KeyModifiers::CONTROL)
+                    | (KeyCode::Esc, _) => {
+                        if saved {
+                            storage::save_game(&game, &autosave)?;
+                        }
+                        break None;
+                    }
+                    (KeyCode::Char(' '), _) if autoplay.is_some() => {
+                        if let Some(autoplay) = &mut autoplay {
+                            autoplay.toggle_pause();
+                        }
+                        continue;
+                    }
+                    (KeyCode::Char('+'), _) | (KeyCode::Char('='), _) if autoplay.is_some() => {
+                        if let Some(autoplay) = &mut autoplay {
+                            autoplay.faster();
+                        }
+                        continue;
+                    }
+                    (KeyCode::Char('-'), _) | (KeyCode::Char('_'), _) if autoplay.is_some() => {
+                        if let Some(autoplay) = &mut autoplay {
+                            autoplay.slower();
+                        }
+                        continue;
+                    }
+                    (KeyCode::Char('u'), _) | (KeyCode::Char('U'), _) => {
+                        notice = if game.undo() {
+                            if let Some(recording) = &mut recording {
+                                recording.undo();
+                            }
+                            None
+                        } else if game.undos_left() == Some(0) {
+                            Some("No undos left")
+                        } else {
+                            Some("Nothing to undo")
+                        };
+                        move_effects.clear();
+                        milestone_checker = MilestoneChecker::new(game.largest_tile());
+                        continue;
+                    }
+                    (KeyCode::Char('r'), _) | (KeyCode::Char('R'), _) => {
+                        notice = if game.redo() {
+                            if let Some(recording) = &mut recording {
+                                recording.redo();
+                            }
+                            None
+                        } else {
+                            Some("Nothing to redo")
+                        };
+                        move_effects.clear();
+                        milestone_checker = MilestoneChecker::new(game.largest_tile());
+                        continue;
+                    }
+                    (KeyCode::Char('h'), _) | (KeyCode::Char('H'), _) => {
+                        hint = solver
+                            .decide_within(game.board(), SEARCH_BUDGET)
+                            .map(|decision| decision.mov);
+                        notice = hint.is_none().then_some("No move changes the board");
+                        continue;
+                    }
+                    (code, _) => direction(code).unwrap_or(Move::Dont),
+                }
+            }
+        };
+        // Playing a move takes control back from the solver
+        if has_event && mov != Move::Dont {
+            autoplay = None;
+        }
 
-            if valid_move {
-                move_effects.record_move(mov, &old_board, game.board());
+        // Player two placing their tile ends the turn without a move
+        let outcome = if placed {
+            placing = None;
+            None
+        } else {
+            game.play(mov)
+        };
+        if !placed {
+            notice = outcome
+                .is_none()
+                .then_some("No tiles moved — try a different direction");
+        }
 
-                game.refresh();
+        if let Some(outcome) = outcome {
+            move_effects.record_move(outcome);
+            if let Some(recording) = &mut recording {
+                recording.record(mov, started.elapsed(), game.score());
+            }
+            if hot_seat {
+                placing = Some(Placement::new(game.board(), game.spawn_rules()));
+            }
 
-                let current_largest = game.largest_tile();
-                if milestone_checker.is_milestone(current_largest) {
-                    terminal.render_board(
-                        &game,
-                        Some(&format!("{current_largest} reached!")),
-                        0,
-                        0,
-                        move_effects.flash(),
-                    )?;
-                }
+            let current_largest = game.largest_tile();
+            if milestone_checker.is_milestone(current_largest) {
+                terminal.render_board(
+                    &game,
+                    &Hud {
+                        best,
+                        message: Some(&format!("{current_largest} reached!")),
+                        ..Default::default()
+                    },
+                    &move_effects,
+                )?;
             }
+        }
+        if placing.is_some() {
+            continue;
+        }
 
-            match game.status() {
-                Status::On => continue,
-                status => {
-                    let end_msg = match status {
-                        Status::Won => "You won!  Press any key to exit.",
-                        Status::Lost => "Game over!  Press any key to exit.",
-                        Status::On => unreachable!(),
-                    };
-                    move_effects.clear();
-                    terminal.render_board(&game, Some(end_msg), 0, 0, move_effects.flash())?;
-                    event::read()?;
-                    break;
+        break Some(match game.status() {
+            Status::On | Status::WonContinuing => continue,
+            Status::Won if puzzle.is_some() => "Puzzle solved!",
+            Status::Won => {
+                move_effects.clear();
+                terminal.render_board(
+                    &game,
+                    &Hud {
+                        best,
+                        message: Some("You won!  C: keep going  ·  Q: quit"),
+                        ..Default::default()
+                    },
+                    &move_effects,
+                )?;
+                let keep_going = loop {
+                    if let Event::Key(key) = event::read()? {
+                        match (key.code, key.modifiers) {
+                            (KeyCode::Char('c'), KeyModifiers::CONTROL) => break false,
+                            (KeyCode::Char('c'), _) | (KeyCode::Char('C'), _) => break true,
+                            (KeyCode::Char('q'), _)
+                            | (KeyCode::Char('Q'), _)
+                            | (KeyCode::Esc, _) => break false,
+                            _ => {}
+                        }
+                    }
+                };
+                if keep_going {
+                    game.keep_playing();
+                    continue;
                 }
+                "You won!"
             }
-        }
+            Status::Lost(LossReason::OutOfMoves) if game.has_won() => {
+                "You won, and played on until you ran out of moves!"
+            }
+            Status::Lost(LossReason::OutOfMoves) => "Out of moves!",
+            Status::Lost(LossReason::Locked) if hot_seat => "Player 1 has no moves left!",
+            Status::Lost(LossReason::Locked) if game.has_won() => {
+                "You won, and played on until no moves were left!"
+            }
+            Status::Lost(LossReason::Locked) => "Game over!",
+        });
+    };
+
+    if let (Some(path), Some(recording)) = (&opts.record, &recording) {
+        storage::save_recording(recording, path)?;
+    }
+
+    if let Some(end_msg) = end_msg {
+        // The game is over, it is only recorded now so a win that is played on counts once
+        let rank = if unranked {
+            None
+        } else {
+            high_scores.insert(Entry::of(category, &game, started.elapsed()))
+        };
+        let end_msg = match rank {
+            Some(1) => format!("{end_msg}  New best score!  Press any key to exit."),
+            Some(rank) => format!("{end_msg}  #{rank} high score.  Press any key to exit."),
+            None => format!("{end_msg}  Press any key to exit."),
+        };
+        high_scores.store(&scores_path)?;
+        move_effects.clear();
+        terminal.render_board(
+            &game,
+            &Hud {
+                best,
+                message: Some(&end_msg),
+                ..Default::default()
+            },
+            &move_effects,
+        )?;
+        event::read()?;
+    }
+
+    if daily {
+        let path = Attempts::path()?;
+        let mut attempts = Attempts::load(&path)?;
+        let attempt = Attempt::of(today, &game);
+        attempts.update(attempt.clone());
+        attempts.store(&path)?;
+
+        drop(terminal);
+        println!("{}", attempt.share_line());
     }
 
     Ok(())
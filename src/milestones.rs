@@ -1,10 +1,10 @@
 use twozero48::Tile;
 
 const MILESTONES: [Tile; 4] = [
-    Tile::OneHundredTwentyEight,
-    Tile::FiveHundredTwelve,
-    Tile::OneThousandTwentyFour,
-    Tile::FourHundredNinetySix,
+    Tile::ONE_HUNDRED_TWENTY_EIGHT,
+    Tile::FIVE_HUNDRED_TWELVE,
+    Tile::ONE_THOUSAND_TWENTY_FOUR,
+    Tile::FOUR_THOUSAND_NINETY_SIX,
 ];
 
 /// Milestones are to be celebrated, but they also need to be tracked!
@@ -34,25 +34,25 @@ mod tests {
 
     #[test]
     fn before_first_milestone() {
-        let mut checker = MilestoneChecker::new(Tile::Empty);
-        assert!(!checker.is_milestone(Tile::SixtyFour));
+        let mut checker = MilestoneChecker::new(Tile::EMPTY);
+        assert!(!checker.is_milestone(Tile::SIXTY_FOUR));
     }
 
     #[test]
     fn first_milestone() {
-        let mut checker = MilestoneChecker::new(Tile::SixtyFour);
-        assert!(checker.is_milestone(Tile::OneHundredTwentyEight));
+        let mut checker = MilestoneChecker::new(Tile::SIXTY_FOUR);
+        assert!(checker.is_milestone(Tile::ONE_HUNDRED_TWENTY_EIGHT));
     }
 
     #[test]
     fn milestone_was_already_reached() {
-        let mut checker = MilestoneChecker::new(Tile::OneHundredTwentyEight);
-        assert!(!checker.is_milestone(Tile::OneHundredTwentyEight));
+        let mut checker = MilestoneChecker::new(Tile::ONE_HUNDRED_TWENTY_EIGHT);
+        assert!(!checker.is_milestone(Tile::ONE_HUNDRED_TWENTY_EIGHT));
     }
 
     #[test]
     fn next_milestone() {
-        let mut checker = MilestoneChecker::new(Tile::OneHundredTwentyEight);
-        assert!(checker.is_milestone(Tile::FiveHundredTwelve));
+        let mut checker = MilestoneChecker::new(Tile::ONE_HUNDRED_TWENTY_EIGHT);
+        assert!(checker.is_milestone(Tile::FIVE_HUNDRED_TWELVE));
     }
 }
@@ -0,0 +1,319 @@
+//! Hand-crafted levels: a starting board that may have blocked cells, the tiles that
+//! spawn after every move, in order, and a [`Goal`] to meet, possibly within a number of moves.
+//!
+//! Puzzles are written as JSON, rows of the board as space separated cells where `.` is
+//! empty and `#` is blocked:
+//!
+//! ```json
+//! {
+//!   "name": "Around the pillar",
+//!   "board": ["2 . . 2", ". # # .", ". # # .", "2 . . 2"],
+//!   "spawns": [{ "tile": 2, "at": [0, 1] }, { "tile": 4 }],
+//!   "goal": { "reach": 16 },
+//!   "moves": 8
+//! }
+//! ```
+//!
+//! A spawn without a cell, or whose cell is taken, goes to the first empty cell in reading
+//! order. Goals are `reach` a tile, clear the board down to a number of `tiles`, or `score`
+//! points, and `moves` optionally limits the number of moves.
+
+use std::fmt::Display;
+
+use serde::Deserialize;
+
+use crate::{Board, Cell, Game, Tile, spawn::SequenceSpawner};
+
+/// Files of the puzzles that ship with the game, in the order they are meant to be played
+const PACK: [&str; 6] = [
+    include_str!("../puzzles/01-first-steps.json"),
+    include_str!("../puzzles/02-pillars.json"),
+    include_str!("../puzzles/03-clean-sweep.json"),
+    include_str!("../puzzles/04-corridor.json"),
+    include_str!("../puzzles/05-score-rush.json"),
+    include_str!("../puzzles/06-walled-garden.json"),
+];
+
+/// What has to happen on the board to solve a puzzle
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Goal {
+    /// A tile at least this large is on the board
+    Reach(Tile),
+    /// At most this many tiles are left on the board
+    Clear(usize),
+    /// At least this many points were scored
+    Score(usize),
+}
+
+impl Goal {
+    pub fn is_met(&self, board: &Board, score: usize) -> bool {
+        let tiles = || {
+            board
+                .iter()
+                .flatten()
+                .filter(|tile| **tile != Tile::EMPTY && !tile.is_blocked())
+        };
+        match *self {
+            Goal::Reach(tile) => tiles().any(|&on_board| on_board >= tile),
+            Goal::Clear(count) => tiles().count() <= count,
+            Goal::Score(points) => score >= points,
+        }
+    }
+}
+
+impl Display for Goal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Goal::Reach(tile) => write!(f, "reach {tile}"),
+            Goal::Clear(1) => write!(f, "clear the board down to 1 tile"),
+            Goal::Clear(count) => write!(f, "clear the board down to {count} tiles"),
+            Goal::Score(points) => write!(f, "score {points} points"),
+        }
+    }
+}
+
+/// On-disk representation of a [`Puzzle`], tiles are written by their value
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PuzzleFile {
+    name: String,
+    #[serde(default)]
+    description: String,
+    board: Vec<String>,
+    #[serde(default)]
+    spawns: Vec<SpawnFile>,
+    goal: GoalFile,
+    #[serde(default)]
+    moves: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpawnFile {
+    tile: usize,
+    #[serde(default)]
+    at: Option<Cell>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum GoalFile {
+    Reach(usize),
+    Tiles(usize),
+    Score(usize),
+}
+
+/// A level to solve, see the [module](self) documentation for the file format
+#[derive(Clone, Debug, PartialEq)]
+pub struct Puzzle {
+    pub name: String,
+    pub description: String,
+    board: Board,
+    spawns: Vec<(Tile, Option<Cell>)>,
+    goal: Goal,
+    moves: Option<usize>,
+}
+
+impl Puzzle {
+    /// Reads a puzzle file, checking it describes a puzzle that isn't solved already
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let file: PuzzleFile = serde_json::from_str(json).map_err(|e| e.to_string())?;
+
+        let board: Board = file
+            .board
+            .iter()
+            .map(|row| row.split_whitespace().map(parse_cell).collect())
+            .collect::<Result<_, _>>()?;
+        let height = board.len();
+        let width = board.first().map_or(0, Vec::len);
+        if width < 2 || height < 2 {
+            return Err("the board has to be 2x2 or more".to_string());
+        }
+        if let Some(r) = board.iter().position(|row| row.len() != width) {
+            return Err(format!(
+                "row {} has {} cells, the first one has {width}",
+                r + 1,
+                board[r].len()
+            ));
+        }
+
+        let spawns = file
+            .spawns
+            .iter()
+            .map(|spawn| {
+                let tile = Tile::from_score(spawn.tile)
+                    .filter(|&tile| tile != Tile::EMPTY)
+                    .ok_or_else(|| format!("{} is not a tile that can spawn", spawn.tile))?;
+                match spawn.at {
+                    Some((r, c)) if r >= height || c >= width => {
+                        Err(format!("({r}, {c}) is off the {width}x{height} board"))
+                    }
+                    at => Ok((tile, at)),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        let goal = match file.goal {
+            GoalFile::Reach(value) => Goal::Reach(
+                Tile::from_score(value)
+                    .filter(|&tile| tile != Tile::EMPTY)
+                    .ok_or_else(|| format!("{value} is not a tile value"))?,
+            ),
+            GoalFile::Tiles(count) => Goal::Clear(count),
+            GoalFile::Score(points) => Goal::Score(points),
+        };
+        if goal.is_met(&board, 0) {
+            return Err(format!("the goal to {goal} is met before the first move"));
+        }
+        if file.moves == Some(0) {
+            return Err("at least one move has to be allowed".to_string());
+        }
+
+        Ok(Self {
+            name: file.name,
+            description: file.description,
+            board,
+            spawns,
+            goal,
+            moves: file.moves,
+        })
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn goal(&self) -> Goal {
+        self.goal
+    }
+
+    /// Returns the number of moves the goal has to be met in, `None` if there is no limit
+    pub fn moves(&self) -> Option<usize> {
+        self.moves
+    }
+
+    /// Starts a game of the puzzle, won once its goal is met
+    pub fn game(&self) -> Game {
+        let winning = match self.goal {
+            Goal::Reach(tile) => tile,
+            // The goal decides when the game is won, this tile is never reached
+            _ => Tile(Tile::MAX_EXPONENT),
+        };
+        let mut game =
+            Game::from_board(self.board.clone(), winning, 0).expect("the board was checked");
+        game.set_spawner(Box::new(SequenceSpawner::new(self.spawns.clone())));
+        game.set_goal(self.goal);
+        if let Some(moves) = self.moves {
+            game.limit_moves(moves);
+        }
+
+        game
+    }
+}
+
+/// Parses a cell of a board row: `.` or `0` is empty, `#` is blocked and anything else a value
+fn parse_cell(cell: &str) -> Result<Tile, String> {
+    match cell {
+        "." => Ok(Tile::EMPTY),
+        "#" => Ok(Tile::BLOCKED),
+        value => value
+            .parse()
+            .ok()
+            .and_then(Tile::from_score)
+            .ok_or_else(|| format!("{value} is not a cell, use ., # or a tile value like 2")),
+    }
+}
+
+/// The puzzles that ship with the game, in the order they are meant to be played
+pub fn builtin() -> Vec<Puzzle> {
+    PACK.iter()
+        .map(|json| Puzzle::parse(json).expect("built-in puzzles are valid"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Move, Status};
+
+    /// Whether some sequence of moves meets the goal before the moves run out, or within
+    /// `depth` moves for puzzles without a limit
+    fn solvable(game: &mut Game, depth: usize) -> bool {
+        if game.has_won() {
+            return true;
+        }
+        if depth == 0 {
+            return false;
+        }
+        Move::ALL.into_iter().any(|mov| {
+            if game.play(mov).is_none() {
+                return false;
+            }
+            let solved = solvable(game, depth - 1);
+            assert!(game.undo());
+            solved
+        })
+    }
+
+    #[test]
+    fn cells_are_parsed() {
+        assert_eq!(parse_cell("."), Ok(Tile::EMPTY));
+        assert_eq!(parse_cell("0"), Ok(Tile::EMPTY));
+        assert_eq!(parse_cell("#"), Ok(Tile::BLOCKED));
+        assert_eq!(parse_cell("64"), Ok(Tile::SIXTY_FOUR));
+        assert!(parse_cell("3").is_err());
+        assert!(parse_cell("x").is_err());
+    }
+
+    #[test]
+    fn invalid_puzzles_are_rejected() {
+        let puzzle = |board: &str, goal: &str| {
+            Puzzle::parse(&format!(
+                r#"{{"name": "test", "board": {board}, "goal": {goal}}}"#
+            ))
+        };
+        assert!(puzzle(r#"["2 2", "4 ."]"#, r#"{"reach": 8}"#).is_ok());
+        assert!(puzzle(r#"["2 2", "4"]"#, r#"{"reach": 8}"#).is_err());
+        assert!(puzzle(r#"["2 2"]"#, r#"{"reach": 8}"#).is_err());
+        assert!(puzzle(r#"["2 2", "4 ."]"#, r#"{"reach": 4}"#).is_err());
+        assert!(puzzle(r#"["2 2", "4 ."]"#, r#"{"tiles": 3}"#).is_err());
+        assert!(puzzle(r#"["2 2", "4 ."]"#, r#"{"reach": 6}"#).is_err());
+        assert!(puzzle(r#"["2 2", "4 ."]"#, r#"{"win": 8}"#).is_err());
+        assert!(
+            Puzzle::parse(
+                r#"{"name": "test", "board": ["2 .", ". ."], "spawns": [{"tile": 2, "at": [2, 0]}], "goal": {"reach": 8}}"#
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn goals_win_the_game() {
+        let mut game = Puzzle::parse(
+            r##"{"name": "test", "board": ["2 # 2", ". . ."], "goal": {"tiles": 1}, "moves": 2}"##,
+        )
+        .unwrap()
+        .game();
+        // The blocked cell keeps the 2s apart
+        assert!(game.play(Move::Left).is_none());
+        assert!(game.play(Move::Down).is_some());
+        assert_eq!(game.status(), Status::On);
+        assert!(game.play(Move::Left).is_some());
+        assert_eq!(game.status(), Status::Won);
+        assert_eq!(game.score(), 4);
+    }
+
+    #[test]
+    fn builtin_puzzles_can_be_solved() {
+        let puzzles = builtin();
+        assert_eq!(puzzles.len(), PACK.len());
+        for puzzle in puzzles {
+            let mut game = puzzle.game();
+            assert!(
+                solvable(&mut game, puzzle.moves().unwrap_or(12)),
+                "{} can't be solved",
+                puzzle.name
+            );
+        }
+    }
+}
@@ -0,0 +1,338 @@
+//! Move logs of whole games. A game started from a seed always plays out the same for the
+//! same moves, so its rules, seed and moves are enough to watch it again or verify its score.
+
+use std::{fmt::Display, io, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Game, Move, Tile, save::SpawnSave, spawn};
+
+/// Version of the recording format, bumped whenever its layout changes
+pub const RECORDING_VERSION: u32 = 1;
+
+/// A move as it was played
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedMove {
+    #[serde(rename = "move")]
+    pub mov: Move,
+    /// Milliseconds since the game started
+    pub ms: u64,
+    /// Score right after the move, to tell where a replay diverges
+    pub score: usize,
+}
+
+/// Everything needed to play a [`Game`] again: the rules it was started with and
+/// the moves that were played, moves that were undone are left out.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    version: u32,
+    width: usize,
+    height: usize,
+    winning: usize,
+    spawn: SpawnSave,
+    spawner: String,
+    seed: u64,
+    #[serde(default)]
+    move_limit: Option<usize>,
+    moves: Vec<RecordedMove>,
+    /// Score the game ended with
+    score: usize,
+    /// Moves taken back, in case they are redone
+    #[serde(skip)]
+    undone: Vec<RecordedMove>,
+}
+
+/// Where replaying a [`Recording`] stopped matching it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Divergence {
+    /// The move at this index didn't change the board
+    Stuck { index: usize, mov: Move },
+    /// The move at this index scored differently
+    Score {
+        index: usize,
+        mov: Move,
+        recorded: usize,
+        replayed: usize,
+    },
+    /// Every move matched, yet the final score doesn't
+    FinalScore { recorded: usize, replayed: usize },
+}
+
+impl Divergence {
+    /// Number of moves that replay as recorded
+    pub fn matching_moves(&self, recording: &Recording) -> usize {
+        match *self {
+            Divergence::Stuck { index, .. } | Divergence::Score { index, .. } => index,
+            Divergence::FinalScore { .. } => recording.moves.len(),
+        }
+    }
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Divergence::Stuck { index, mov } => {
+                write!(f, "move {} ({mov:?}) doesn't change the board", index + 1)
+            }
+            Divergence::Score {
+                index,
+                mov,
+                recorded,
+                replayed,
+            } => write!(
+                f,
+                "move {} ({mov:?}) scores {replayed} instead of the recorded {recorded}",
+                index + 1
+            ),
+            Divergence::FinalScore { recorded, replayed } => write!(
+                f,
+                "the game ends with {replayed} points instead of the recorded {recorded}"
+            ),
+        }
+    }
+}
+
+impl Recording {
+    /// Starts recording a game that hasn't been played yet. Only games started from a seed
+    /// with a built-in spawner can be replayed, e.g. not puzzles or resumed games
+    pub fn start(game: &Game) -> Result<Self, String> {
+        let seed = game
+            .seed()
+            .ok_or("only games started from a seed can be recorded")?;
+        if spawn::builtin(game.spawner().name()).is_none() || game.goal().is_some() {
+            return Err("only games with random, evil or friendly spawns can be recorded".into());
+        }
+
+        let recording = Self {
+            version: RECORDING_VERSION,
+            width: game.size().width,
+            height: game.size().height,
+            winning: game.winning().score(),
+            spawn: SpawnSave::of(game.spawn_rules()),
+            spawner: game.spawner().name().to_string(),
+            seed,
+            move_limit: game.move_limit(),
+            moves: vec![],
+            score: 0,
+            undone: vec![],
+        };
+        if game.moves() > 0 || recording.new_game()?.board() != game.board() {
+            return Err("only games that haven't been played yet can be recorded".into());
+        }
+
+        Ok(recording)
+    }
+
+    /// Adds a move that changed the board, `at` is the time since the game started
+    pub fn record(&mut self, mov: Move, at: Duration, score: usize) {
+        self.moves.push(RecordedMove {
+            mov,
+            ms: at.as_millis() as u64,
+            score,
+        });
+        self.undone.clear();
+        self.score = score;
+    }
+
+    /// Takes back the last move, like [`Game::undo`]
+    pub fn undo(&mut self) {
+        if let Some(mov) = self.moves.pop() {
+            self.undone.push(mov);
+            self.score = self.moves.last().map_or(0, |mov| mov.score);
+        }
+    }
+
+    /// Adds back the last move taken back, like [`Game::redo`]
+    pub fn redo(&mut self) {
+        if let Some(mov) = self.undone.pop() {
+            self.score = mov.score;
+            self.moves.push(mov);
+        }
+    }
+
+    pub fn moves(&self) -> &[RecordedMove] {
+        &self.moves
+    }
+
+    /// Returns the score the game ended with
+    pub fn score(&self) -> usize {
+        self.score
+    }
+
+    /// Constructs the game as it was before the first move
+    pub fn new_game(&self) -> Result<Game, String> {
+        let winning = Tile::from_score(self.winning)
+            .filter(|&tile| tile != Tile::EMPTY)
+            .ok_or_else(|| format!("{} is not a tile value", self.winning))?;
+        let spawner = spawn::builtin(&self.spawner)
+            .ok_or_else(|| format!("{} is not a built-in spawner", self.spawner))?;
+        if self.width < 2 || self.height < 2 {
+            return Err(format!(
+                "a {}x{} board is too small",
+                self.width, self.height
+            ));
+        }
+
+        let mut game = Game::with_rules(
+            (self.width, self.height),
+            winning,
+            self.spawn.rules()?,
+            self.seed,
+        );
+        game.set_spawner(spawner);
+        if let Some(limit) = self.move_limit {
+            game.limit_moves(limit);
+        }
+
+        Ok(game)
+    }
+
+    /// Plays the first `count` moves on the game, stopping at the first one that doesn't
+    /// replay as recorded
+    pub fn replay(&self, game: &mut Game, count: usize) -> Result<(), Divergence> {
+        for (index, recorded) in self.moves.iter().enumerate().take(count) {
+            let mov = recorded.mov;
+            if !game.mover(mov) {
+                return Err(Divergence::Stuck { index, mov });
+            }
+            game.refresh();
+            if game.score() != recorded.score {
+                return Err(Divergence::Score {
+                    index,
+                    mov,
+                    recorded: recorded.score,
+                    replayed: game.score(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replays every move from the start, checking the game ends as recorded
+    pub fn verify(&self) -> Result<Game, Divergence> {
+        let mut game = self
+            .new_game()
+            .expect("recordings are checked when they are started or loaded");
+        self.replay(&mut game, self.moves.len())?;
+        if game.score() != self.score {
+            return Err(Divergence::FinalScore {
+                recorded: self.score,
+                replayed: game.score(),
+            });
+        }
+
+        Ok(game)
+    }
+
+    pub fn save<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        serde_json::to_writer(writer, self).map_err(io::Error::other)
+    }
+
+    /// Reads a recording written by [`Recording::save`], checking it can be replayed
+    pub fn load<Rd: io::Read>(reader: Rd) -> Result<Self, String> {
+        let recording: Self =
+            serde_json::from_reader(reader).map_err(|e| format!("recording is corrupt: {e}"))?;
+        if recording.version != RECORDING_VERSION {
+            return Err(format!(
+                "recording has format version {}, only version {RECORDING_VERSION} is supported",
+                recording.version
+            ));
+        }
+        recording
+            .new_game()
+            .map_err(|e| format!("recording is invalid: {e}"))?;
+
+        Ok(recording)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Status;
+
+    fn recorded_game() -> Recording {
+        let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 5);
+        let mut recording = Recording::start(&game).unwrap();
+        let mut t = 0;
+        while let Status::On = game.status() {
+            let mov = Move::ALL.into_iter().find(|&mov| game.mover(mov)).unwrap();
+            game.refresh();
+            t += 150;
+            recording.record(mov, Duration::from_millis(t), game.score());
+        }
+        recording
+    }
+
+    #[test]
+    fn recordings_replay_the_game() {
+        let recording = recorded_game();
+        let mut buf = vec![];
+        recording.save(&mut buf).unwrap();
+        let loaded = Recording::load(buf.as_slice()).unwrap();
+        assert_eq!(loaded, recording);
+
+        let game = loaded.verify().unwrap();
+        assert_eq!(game.score(), recording.score());
+        assert_eq!(game.moves(), recording.moves().len());
+    }
+
+    #[test]
+    fn first_divergent_move_is_reported() {
+        let mut recording = recorded_game();
+        recording.moves[10].score += 4;
+        assert!(matches!(
+            recording.verify(),
+            Err(Divergence::Score { index: 10, .. })
+        ));
+
+        let mut recording = recorded_game();
+        recording.moves.swap(3, 4);
+        let Err(divergence) = recording.verify() else {
+            panic!("swapped moves replayed as recorded");
+        };
+        assert!(divergence.matching_moves(&recording) >= 3);
+
+        let mut recording = recorded_game();
+        recording.score += 2;
+        assert!(matches!(
+            recording.verify(),
+            Err(Divergence::FinalScore { .. })
+        ));
+    }
+
+    #[test]
+    fn undone_moves_are_left_out() {
+        let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 5);
+        let mut recording = Recording::start(&game).unwrap();
+        for mov in [Move::Left, Move::Up, Move::Right] {
+            if game.mover(mov) {
+                game.refresh();
+                recording.record(mov, Duration::ZERO, game.score());
+            }
+        }
+        assert!(game.undo());
+        recording.undo();
+        assert!(game.undo());
+        recording.undo();
+        assert!(game.redo());
+        recording.redo();
+        assert!(game.mover(Move::Down));
+        game.refresh();
+        recording.record(Move::Down, Duration::ZERO, game.score());
+
+        assert_eq!(recording.verify().unwrap().board(), game.board());
+    }
+
+    #[test]
+    fn played_games_are_not_recorded() {
+        let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 5);
+        assert!(Recording::start(&game).is_ok());
+        assert!(game.mover(Move::Left) || game.mover(Move::Right));
+        assert!(Recording::start(&game).is_err());
+
+        let puzzle = crate::puzzle::builtin().remove(0);
+        assert!(Recording::start(&puzzle.game()).is_err());
+    }
+}
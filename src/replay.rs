@@ -0,0 +1,259 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use twozero48::record::{Divergence, Recording};
+
+use crate::tui::{Hud, MoveEffects, TermGuard};
+
+const MIN_SPEED: f64 = 0.25;
+const MAX_SPEED: f64 = 16.0;
+/// Longest wait between two moves, so a replay doesn't sit through the breaks the player took
+const MAX_GAP: Duration = Duration::from_secs(1);
+
+/// Paces a replay like the game was played, speed multiplies how fast time passes
+pub struct Playback {
+    speed: f64,
+    paused: bool,
+    next_move: Instant,
+}
+
+impl Playback {
+    pub fn new() -> Self {
+        Self {
+            speed: 1.0,
+            paused: false,
+            next_move: Instant::now(),
+        }
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.next_move = Instant::now();
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Doubles the speed
+    pub fn faster(&mut self) {
+        self.speed = (self.speed * 2.0).min(MAX_SPEED);
+    }
+
+    /// Halves the speed
+    pub fn slower(&mut self) {
+        self.speed = (self.speed / 2.0).max(MIN_SPEED);
+    }
+
+    /// Time left until the next move is due, `None` while paused
+    pub fn wait(&self) -> Option<Duration> {
+        (!self.paused).then(|| self.next_move.saturating_duration_since(Instant::now()))
+    }
+
+    pub fn is_due(&self) -> bool {
+        !self.paused && Instant::now() >= self.next_move
+    }
+
+    /// Schedules the next move, `gap` after this one at normal speed
+    pub fn schedule(&mut self, gap: Duration) {
+        self.next_move = Instant::now() + gap.min(MAX_GAP).div_f64(self.speed);
+    }
+}
+
+/// Time the player took between the move at `index` and the one before it
+fn gap(recording: &Recording, index: usize) -> Duration {
+    let moves = recording.moves();
+    let before = index
+        .checked_sub(1)
+        .and_then(|i| moves.get(i))
+        .map_or(0, |mov| mov.ms);
+    let at = moves.get(index).map_or(before, |mov| mov.ms);
+
+    Duration::from_millis(at.saturating_sub(before))
+}
+
+/// Plays the recording back in the terminal, stopping at the first move that diverges
+/// from it. What the verification found is printed once the viewer is closed.
+pub fn run(recording: &Recording, animate: bool) -> io::Result<()> {
+    let verdict = recording.verify().map(|_| ());
+    let total = recording.moves().len();
+    let playable = match &verdict {
+        Ok(()) => total,
+        Err(divergence) => divergence.matching_moves(recording),
+    };
+    let banner = match &verdict {
+        Ok(()) => "VERIFIED".to_string(),
+        Err(Divergence::FinalScore { .. }) => "FINAL SCORE DIVERGES".to_string(),
+        Err(_) => format!("DIVERGES AT MOVE {}", playable + 1),
+    };
+
+    let mut terminal = TermGuard::new()?;
+    let mut game = recording
+        .new_game()
+        .expect("recordings are checked when loaded");
+    let mut position = 0;
+    let mut playback = Playback::new();
+    playback.schedule(gap(recording, 0));
+    let mut effects = MoveEffects::new(animate);
+
+    loop {
+        effects.tick();
+        let message = match &verdict {
+            Err(divergence) if position == playable => {
+                format!("Stopped: {divergence}  ·  ←: step back  ·  Q: quit")
+            }
+            _ => format!(
+                "REPLAY {position}/{total}  ·  {}x  ·  Space: {}  ·  ← / →: step  ·  + / -: speed  ·  Q: quit",
+                playback.speed(),
+                if playback.is_paused() {
+                    "play"
+                } else {
+                    "pause"
+                }
+            ),
+        };
+        terminal.render_board(
+            &game,
+            &Hud {
+                message: Some(&message),
+                banner: Some(&banner),
+                ..Default::default()
+            },
+            &effects,
+        )?;
+
+        let timeout = [effects.next_frame(), playback.wait()]
+            .into_iter()
+            .flatten()
+            .min();
+        let has_event = match timeout {
+            Some(timeout) => event::poll(timeout)?,
+            None => true,
+        };
+
+        let forward = if !has_event {
+            if !playback.is_due() {
+                continue;
+            }
+            true
+        } else {
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            match (key.code, key.modifiers) {
+                (KeyCode::Char('q'), _)
+                | (KeyCode::Char('Q'), _)
+                | (KeyCode::Char('c'), KeyModifiers::CONTROL)
+                | (KeyCode::Esc, _) => break,
+                (KeyCode::Char(' '), _) => {
+                    playback.toggle_pause();
+                    continue;
+                }
+                (KeyCode::Char('+'), _) | (KeyCode::Char('='), _) => {
+                    playback.faster();
+                    continue;
+                }
+                (KeyCode::Char('-'), _) | (KeyCode::Char('_'), _) => {
+                    playback.slower();
+                    continue;
+                }
+                (KeyCode::Right, _) | (KeyCode::Char('d'), _) | (KeyCode::Char('D'), _) => {
+                    playback.pause();
+                    true
+                }
+                (KeyCode::Left, _) | (KeyCode::Char('a'), _) | (KeyCode::Char('A'), _) => {
+                    playback.pause();
+                    if position > 0 {
+                        position -= 1;
+                        game = recording
+                            .new_game()
+                            .expect("recordings are checked when loaded");
+                        recording
+                            .replay(&mut game, position)
+                            .expect("moves before the divergence replay as recorded");
+                        effects.clear();
+                    }
+                    continue;
+                }
+                _ => continue,
+            }
+        };
+
+        if forward && position < playable {
+            if let Some(outcome) = game.play(recording.moves()[position].mov) {
+                effects.record_move(outcome);
+            }
+            position += 1;
+        }
+        if position == playable {
+            playback.pause();
+        } else {
+            playback.schedule(gap(recording, position));
+        }
+    }
+    drop(terminal);
+
+    match verdict {
+        Ok(()) => println!(
+            "Verified: {total} moves replay to the recorded {} points",
+            recording.score()
+        ),
+        Err(divergence) => println!("Diverges from the recording: {divergence}"),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use twozero48::{Game, Move, Tile};
+
+    use super::*;
+
+    #[test]
+    fn speed_stays_within_bounds() {
+        let mut playback = Playback::new();
+        for _ in 0..10 {
+            playback.faster();
+        }
+        assert_eq!(playback.speed(), MAX_SPEED);
+        for _ in 0..20 {
+            playback.slower();
+        }
+        assert_eq!(playback.speed(), MIN_SPEED);
+        playback.pause();
+        assert!(playback.wait().is_none());
+        assert!(!playback.is_due());
+    }
+
+    #[test]
+    fn gaps_between_recorded_moves() {
+        let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 2);
+        let mut recording = Recording::start(&game).unwrap();
+        for (mov, ms) in [(Move::Left, 400), (Move::Right, 700), (Move::Up, 5000)] {
+            if game.mover(mov) {
+                game.refresh();
+                recording.record(mov, Duration::from_millis(ms), game.score());
+            }
+        }
+        let gaps: Vec<Duration> = (0..recording.moves().len())
+            .map(|i| gap(&recording, i))
+            .collect();
+        assert_eq!(gaps[0], Duration::from_millis(400));
+        assert_eq!(
+            gaps.iter().sum::<Duration>(),
+            Duration::from_millis(recording.moves().last().unwrap().ms)
+        );
+    }
+}
@@ -0,0 +1,395 @@
+use std::{error::Error, fmt::Display, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Game, GameRng, Tile,
+    spawn::{self, SpawnRules},
+};
+
+/// Version of the save format, bumped whenever its layout changes
+pub const SAVE_VERSION: u32 = 2;
+/// Oldest version that can still be loaded, version 1 saves had square boards
+/// stored by their `board_size`
+const OLDEST_SAVE_VERSION: u32 = 1;
+
+/// Only the version is read first, so files from other versions are rejected
+/// with a clear error instead of whatever the layout mismatch would produce.
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+/// On-disk representation of a [`Game`] in progress, tiles are stored by their value
+/// and blocked cells as `null`. Undo history is not part of a save.
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+    version: u32,
+    #[serde(default)]
+    width: usize,
+    #[serde(default)]
+    height: usize,
+    /// Side of the square board of version 1 saves
+    #[serde(default, skip_serializing)]
+    board_size: Option<usize>,
+    board: Vec<Vec<Option<usize>>>,
+    winning: usize,
+    #[serde(default)]
+    continued: bool,
+    /// Saves without spawn rules were played by the standard ones
+    #[serde(default)]
+    spawn: Option<SpawnSave>,
+    /// Name of a built-in spawner, saves without one spawned at random
+    #[serde(default)]
+    spawner: Option<String>,
+    score: usize,
+    moves: usize,
+    seed: Option<u64>,
+    undos_left: Option<usize>,
+    #[serde(default)]
+    move_limit: Option<usize>,
+    rng: GameRng,
+}
+
+/// [`SpawnRules`] with tiles stored by their value
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SpawnSave {
+    weights: Vec<(usize, f64)>,
+    count: usize,
+    start_tiles: usize,
+}
+
+impl SpawnSave {
+    pub(crate) fn of(rules: &SpawnRules) -> Self {
+        Self {
+            weights: rules
+                .weights()
+                .iter()
+                .map(|(tile, weight)| (tile.score(), *weight))
+                .collect(),
+            count: rules.count(),
+            start_tiles: rules.start_tiles(),
+        }
+    }
+
+    pub(crate) fn rules(&self) -> Result<SpawnRules, String> {
+        let weights = self
+            .weights
+            .iter()
+            .map(|&(value, weight)| {
+                let tile = Tile::from_score(value)
+                    .ok_or_else(|| format!("{value} is not a tile value"))?;
+                Ok((tile, weight))
+            })
+            .collect::<Result<_, String>>()?;
+
+        SpawnRules::new(weights, self.count, self.start_tiles)
+    }
+}
+
+/// Reasons a saved game could not be loaded
+#[derive(Debug)]
+pub enum LoadError {
+    /// The save couldn't be read
+    Io(io::Error),
+    /// The save is not in the expected format
+    Corrupt(String),
+    /// The save was written by an incompatible version of the format
+    Version { found: u32, expected: u32 },
+    /// The save is well formed but doesn't describe a valid game
+    Invalid(String),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "could not read saved game: {e}"),
+            LoadError::Corrupt(e) => write!(f, "saved game is corrupt: {e}"),
+            LoadError::Version { found, expected } => write!(
+                f,
+                "saved game has format version {found}, only versions up to {expected} are supported"
+            ),
+            LoadError::Invalid(e) => write!(f, "saved game is invalid: {e}"),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+impl Game {
+    /// Writes the game in the versioned save format. Games [`Game::load`] couldn't restore,
+    /// those with a goal or placed by a spawner that isn't built in, are refused with an
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) error and nothing is written
+    pub fn save<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        if self.goal.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "games with a goal can't be saved",
+            ));
+        }
+        let spawner = self.spawner.name();
+        if spawn::builtin(spawner).is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("games placed by the {spawner} spawner can't be saved"),
+            ));
+        }
+
+        let save = SaveFile {
+            version: SAVE_VERSION,
+            width: self.size.width,
+            height: self.size.height,
+            board_size: None,
+            board: self
+                .board
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|tile| (!tile.is_blocked()).then(|| tile.score()))
+                        .collect()
+                })
+                .collect(),
+            winning: self.winning.score(),
+            continued: self.continued,
+            spawn: Some(SpawnSave::of(&self.rules)),
+            spawner: Some(spawner.to_string()),
+            score: self.score,
+            moves: self.moves,
+            seed: self.seed,
+            undos_left: self.undos_left,
+            move_limit: self.move_limit,
+            rng: self.rng.clone(),
+        };
+
+        serde_json::to_writer(writer, &save).map_err(io::Error::other)
+    }
+
+    /// Restores a game written by [`Game::save`], continuing with the same spawns
+    pub fn load<Rd: io::Read>(mut reader: Rd) -> Result<Self, LoadError> {
+        let mut contents = String::new();
+        reader
+            .read_to_string(&mut contents)
+            .map_err(LoadError::Io)?;
+
+        let header: Header =
+            serde_json::from_str(&contents).map_err(|e| LoadError::Corrupt(e.to_string()))?;
+        if !(OLDEST_SAVE_VERSION..=SAVE_VERSION).contains(&header.version) {
+            return Err(LoadError::Version {
+                found: header.version,
+                expected: SAVE_VERSION,
+            });
+        }
+
+        let mut save: SaveFile =
+            serde_json::from_str(&contents).map_err(|e| LoadError::Corrupt(e.to_string()))?;
+        if let Some(size) = save.board_size {
+            save.width = size;
+            save.height = size;
+        }
+
+        if save.width < 2
+            || save.height < 2
+            || save.board.len() != save.height
+            || save.board.iter().any(|row| row.len() != save.width)
+        {
+            return Err(LoadError::Invalid(format!(
+                "board is not {}x{}",
+                save.width, save.height
+            )));
+        }
+        let tile = |value| {
+            Tile::from_score(value)
+                .ok_or_else(|| LoadError::Invalid(format!("{value} is not a tile value")))
+        };
+        let board = save
+            .board
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.map_or(Ok(Tile::BLOCKED), tile))
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+        let winning = tile(save.winning)?;
+        let rules = match save.spawn {
+            Some(spawn) => spawn.rules().map_err(LoadError::Invalid)?,
+            None => SpawnRules::default(),
+        };
+        let spawner = save.spawner.as_deref().unwrap_or("random");
+        let spawner = spawn::builtin(spawner)
+            .ok_or_else(|| LoadError::Invalid(format!("{spawner} is not a built-in spawner")))?;
+
+        let mut game = Game::empty((save.width, save.height).into(), winning, rules, save.rng);
+        game.board = board;
+        game.spawner = spawner;
+        game.continued = save.continued;
+        game.score = save.score;
+        game.moves = save.moves;
+        game.seed = save.seed;
+        game.undos_left = save.undos_left;
+        game.move_limit = save.move_limit;
+
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Move;
+
+    #[test]
+    fn save_then_load_continues_the_same_game() {
+        let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 11);
+        game.limit_moves(50);
+        for mov in [Move::Left, Move::Up, Move::Right, Move::Down] {
+            if game.mover(mov) {
+                game.refresh();
+            }
+        }
+
+        let mut buf = vec![];
+        game.save(&mut buf).unwrap();
+        let mut loaded = Game::load(buf.as_slice()).unwrap();
+        assert_eq!(loaded.board(), game.board());
+        assert_eq!(loaded.score(), game.score());
+        assert_eq!(loaded.moves(), game.moves());
+        assert_eq!(loaded.seed(), Some(11));
+        assert_eq!(loaded.moves_left(), game.moves_left());
+
+        for mov in [Move::Left, Move::Down, Move::Right, Move::Up] {
+            if game.mover(mov) {
+                game.refresh();
+            }
+            if loaded.mover(mov) {
+                loaded.refresh();
+            }
+        }
+        assert_eq!(loaded.board(), game.board());
+    }
+
+    #[test]
+    fn spawn_rules_are_saved() {
+        let rules = SpawnRules::new(vec![(Tile::EIGHT, 1.0)], 2, 3).unwrap();
+        let game = Game::with_rules(4, Tile::TWO_THOUSAND_FORTY_EIGHT, rules.clone(), 4);
+        let mut buf = vec![];
+        game.save(&mut buf).unwrap();
+        assert_eq!(Game::load(buf.as_slice()).unwrap().spawn_rules(), &rules);
+
+        let mut save: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        save.as_object_mut().unwrap().remove("spawn");
+        let loaded = Game::load(save.to_string().as_bytes()).unwrap();
+        assert_eq!(loaded.spawn_rules(), &SpawnRules::default());
+    }
+
+    #[test]
+    fn spawner_is_saved() {
+        let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 4);
+        game.set_spawner(spawn::builtin("evil").unwrap());
+        let mut buf = vec![];
+        game.save(&mut buf).unwrap();
+        assert_eq!(Game::load(buf.as_slice()).unwrap().spawner().name(), "evil");
+
+        let mut save: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        save["spawner"] = "psychic".into();
+        let loaded = Game::load(save.to_string().as_bytes());
+        assert!(matches!(loaded, Err(LoadError::Invalid(_))));
+    }
+
+    #[test]
+    fn games_that_cant_be_loaded_arent_saved() {
+        let mut buf = vec![];
+        let puzzle = crate::puzzle::builtin().remove(0).game();
+        let saved = puzzle.save(&mut buf);
+        assert_eq!(saved.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+        let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 4);
+        game.set_spawner(Box::new(spawn::SequenceSpawner::new(vec![(
+            Tile::TWO,
+            None,
+        )])));
+        let saved = game.save(&mut buf);
+        assert_eq!(saved.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn blocked_cells_are_saved() {
+        let board = vec![
+            vec![Tile::TWO, Tile::BLOCKED],
+            vec![Tile::EMPTY, Tile::FOUR],
+        ];
+        let game = Game::from_board(board, Tile::TWO_THOUSAND_FORTY_EIGHT, 1).unwrap();
+        let mut buf = vec![];
+        game.save(&mut buf).unwrap();
+        let save: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert!(save["board"][0][1].is_null());
+        assert_eq!(Game::load(buf.as_slice()).unwrap().board(), game.board());
+    }
+
+    #[test]
+    fn load_rejects_other_versions() {
+        let loaded = Game::load(r#"{"version": 99}"#.as_bytes());
+        assert!(matches!(
+            loaded,
+            Err(LoadError::Version {
+                found: 99,
+                expected: SAVE_VERSION
+            })
+        ));
+    }
+
+    #[test]
+    fn version_1_saves_are_loaded() {
+        let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 6);
+        for mov in [Move::Left, Move::Up] {
+            if game.mover(mov) {
+                game.refresh();
+            }
+        }
+        let mut buf = vec![];
+        game.save(&mut buf).unwrap();
+
+        // Only the fields version 1 had, with the board size in place of width and height
+        let save: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        let mut old = serde_json::json!({"version": 1, "board_size": 4});
+        for field in [
+            "board",
+            "winning",
+            "score",
+            "moves",
+            "seed",
+            "undos_left",
+            "rng",
+        ] {
+            old[field] = save[field].clone();
+        }
+        let mut loaded = Game::load(old.to_string().as_bytes()).unwrap();
+        assert_eq!(loaded.size(), game.size());
+        assert_eq!(loaded.board(), game.board());
+        assert_eq!(loaded.score(), game.score());
+
+        assert_eq!(loaded.mover(Move::Down), game.mover(Move::Down));
+        loaded.refresh();
+        game.refresh();
+        assert_eq!(loaded.board(), game.board());
+    }
+
+    #[test]
+    fn load_rejects_corrupt_files() {
+        let loaded = Game::load("not a save".as_bytes());
+        assert!(matches!(loaded, Err(LoadError::Corrupt(_))));
+    }
+
+    #[test]
+    fn load_rejects_invalid_tiles() {
+        let game = Game::with_seed(2, Tile::TWO_THOUSAND_FORTY_EIGHT, 1);
+        let mut buf = vec![];
+        game.save(&mut buf).unwrap();
+        let mut save: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        save["board"][0][0] = 3.into();
+
+        let loaded = Game::load(save.to_string().as_bytes());
+        assert!(matches!(loaded, Err(LoadError::Invalid(_))));
+    }
+}
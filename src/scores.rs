@@ -0,0 +1,286 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use twozero48::Game;
+
+use crate::storage;
+
+const SCORES_FILE: &str = "scores.json";
+/// Number of scores kept for every set of rules
+const TOP_N: usize = 10;
+
+/// Rules a score was achieved under, scores are only ever compared within the same rules
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "CategoryFile")]
+pub struct Category {
+    pub width: usize,
+    pub height: usize,
+    pub winning: usize,
+    /// Describes the mode for scores of modes other than classic, e.g. "time attack 120s"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+}
+
+/// A [`Category`] as stored, older tables kept square boards by their `board_size`
+#[derive(Deserialize)]
+struct CategoryFile {
+    #[serde(default)]
+    width: usize,
+    #[serde(default)]
+    height: usize,
+    #[serde(default)]
+    board_size: Option<usize>,
+    winning: usize,
+    #[serde(default)]
+    mode: Option<String>,
+}
+
+impl From<CategoryFile> for Category {
+    fn from(file: CategoryFile) -> Self {
+        let (width, height) = match file.board_size {
+            Some(size) => (size, size),
+            None => (file.width, file.height),
+        };
+
+        Self {
+            width,
+            height,
+            winning: file.winning,
+            mode: file.mode,
+        }
+    }
+}
+
+impl Category {
+    /// Category of a game, games with a move limit are in a category of their own
+    pub fn of(game: &Game) -> Self {
+        Self {
+            width: game.size().width,
+            height: game.size().height,
+            winning: game.winning().score(),
+            mode: game
+                .move_limit()
+                .map(|limit| format!("within {limit} moves")),
+        }
+    }
+
+    pub fn in_mode(self, mode: String) -> Self {
+        Self {
+            mode: Some(mode),
+            ..self
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Entry {
+    pub category: Category,
+    pub date: String,
+    pub score: usize,
+    pub largest_tile: usize,
+    pub moves: usize,
+    pub duration_secs: u64,
+}
+
+impl Entry {
+    pub fn of(category: Category, game: &Game, duration: Duration) -> Self {
+        Self {
+            category,
+            date: time::OffsetDateTime::now_utc().date().to_string(),
+            score: game.score(),
+            largest_tile: game.largest_tile().score(),
+            moves: game.moves(),
+            duration_secs: duration.as_secs(),
+        }
+    }
+}
+
+/// Local high-score table, keeping the [`TOP_N`] scores of every [`Category`]
+#[derive(Default, Serialize, Deserialize)]
+pub struct HighScores {
+    entries: Vec<Entry>,
+}
+
+impl HighScores {
+    pub fn path() -> io::Result<PathBuf> {
+        Ok(storage::data_dir()?.join(SCORES_FILE))
+    }
+
+    /// Reads the table, a missing file is an empty table
+    pub fn load(path: &Path) -> io::Result<Self> {
+        storage::load_json(path)
+    }
+
+    pub fn store(&self, path: &Path) -> io::Result<()> {
+        storage::store_json(self, path)
+    }
+
+    /// Scores of a category, best first
+    pub fn top<'a>(&'a self, category: &'a Category) -> impl Iterator<Item = &'a Entry> {
+        self.entries.iter().filter(move |e| &e.category == category)
+    }
+
+    pub fn best(&self, category: &Category) -> Option<usize> {
+        self.top(category).map(|e| e.score).next()
+    }
+
+    /// Records a score, returns its rank in the category if it made the table
+    pub fn insert(&mut self, entry: Entry) -> Option<usize> {
+        let rank = self
+            .top(&entry.category)
+            .filter(|e| e.score >= entry.score)
+            .count();
+        if rank >= TOP_N {
+            return None;
+        }
+
+        let category = entry.category.clone();
+        let at = self
+            .entries
+            .iter()
+            .position(|e| e.category == category && e.score < entry.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(at, entry);
+
+        // Drop whatever was pushed out of the category's table
+        let mut kept = 0;
+        self.entries.retain(|e| {
+            if e.category != category {
+                return true;
+            }
+            kept += 1;
+            kept <= TOP_N
+        });
+
+        Some(rank + 1)
+    }
+
+    /// All categories with scores, ordered by board dimensions, winning tile then mode
+    pub fn categories(&self) -> Vec<Category> {
+        let mut categories: Vec<Category> = vec![];
+        for entry in &self.entries {
+            if !categories.contains(&entry.category) {
+                categories.push(entry.category.clone());
+            }
+        }
+        categories.sort_by(|a, b| {
+            (a.width, a.height, a.winning, &a.mode).cmp(&(b.width, b.height, b.winning, &b.mode))
+        });
+        categories
+    }
+}
+
+/// Prints the high-score tables for the `scores` subcommand
+pub fn print(scores: &HighScores) {
+    let categories = scores.categories();
+    if categories.is_empty() {
+        println!("No scores yet, finish a game to get on the board!");
+        return;
+    }
+
+    for category in categories {
+        match &category.mode {
+            Some(mode) => println!(
+                "{}x{} board, winning at {}, {mode}",
+                category.width, category.height, category.winning
+            ),
+            None => println!(
+                "{}x{} board, winning at {}",
+                category.width, category.height, category.winning
+            ),
+        }
+        println!(
+            "{:>4}  {:>8}  {:>6}  {:>6}  {:>8}  {:<10}",
+            "#", "SCORE", "TILE", "MOVES", "TIME", "DATE"
+        );
+        for (i, entry) in scores.top(&category).enumerate() {
+            let (mins, secs) = (entry.duration_secs / 60, entry.duration_secs % 60);
+            println!(
+                "{:>4}  {:>8}  {:>6}  {:>6}  {:>5}:{:02}  {:<10}",
+                i + 1,
+                entry.score,
+                entry.largest_tile,
+                entry.moves,
+                mins,
+                secs,
+                entry.date
+            );
+        }
+        println!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(board_size: usize, score: usize) -> Entry {
+        Entry {
+            category: Category {
+                width: board_size,
+                height: board_size,
+                winning: 2048,
+                mode: None,
+            },
+            date: "2026-01-01".to_string(),
+            score,
+            largest_tile: 256,
+            moves: 100,
+            duration_secs: 60,
+        }
+    }
+
+    #[test]
+    fn scores_are_ranked_within_category() {
+        let mut scores = HighScores::default();
+        assert_eq!(scores.insert(entry(4, 100)), Some(1));
+        assert_eq!(scores.insert(entry(4, 300)), Some(1));
+        assert_eq!(scores.insert(entry(4, 200)), Some(2));
+        assert_eq!(scores.insert(entry(5, 50)), Some(1));
+
+        let four = entry(4, 0).category;
+        let ranked: Vec<usize> = scores.top(&four).map(|e| e.score).collect();
+        assert_eq!(ranked, vec![300, 200, 100]);
+        assert_eq!(scores.best(&entry(5, 0).category), Some(50));
+
+        let timed = entry(4, 0).category.in_mode("time attack 120s".to_string());
+        assert_eq!(scores.best(&timed), None);
+    }
+
+    #[test]
+    fn table_keeps_top_n() {
+        let mut scores = HighScores::default();
+        for score in 1..=TOP_N {
+            scores.insert(entry(4, score * 10));
+        }
+        assert_eq!(scores.insert(entry(4, 5)), None);
+        assert_eq!(scores.insert(entry(4, 55)), Some(6));
+
+        let four = entry(4, 0).category;
+        assert_eq!(scores.top(&four).count(), TOP_N);
+        assert_eq!(scores.top(&four).last().map(|e| e.score), Some(20));
+    }
+
+    #[test]
+    fn tables_with_board_sizes_are_loaded() {
+        let old = r#"{"entries": [{
+            "category": {"board_size": 5, "winning": 2048},
+            "date": "2026-01-01",
+            "score": 1200,
+            "largest_tile": 128,
+            "moves": 150,
+            "duration_secs": 90
+        }]}"#;
+        let scores: HighScores = serde_json::from_str(old).unwrap();
+        assert_eq!(scores.best(&entry(5, 0).category), Some(1200));
+
+        let stored = serde_json::to_string(&scores).unwrap();
+        assert!(!stored.contains("board_size"));
+        let reloaded: HighScores = serde_json::from_str(&stored).unwrap();
+        assert_eq!(reloaded.categories(), scores.categories());
+    }
+}
@@ -0,0 +1,543 @@
+use std::{
+    fmt::Display,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    net::{IpAddr, SocketAddr, TcpListener, TcpStream},
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use clap::{Args, value_parser};
+use serde::{Deserialize, Serialize};
+use twozero48::{
+    Status,
+    engine::{self, ErrorCode, GameOptions, Response, Session},
+};
+
+use crate::storage;
+
+const RESULTS_FILE: &str = "results.jsonl";
+/// Longest request line a client may send, anything longer ends its session
+const MAX_LINE: u64 = 4096;
+/// Time a client gets to read a response before its session is ended
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Options of the `serve` subcommand
+#[derive(Args)]
+pub struct ServeOpts {
+    /// Port to listen on, 0 picks a free one
+    #[clap(long, default_value = "7048")]
+    pub port: u16,
+    /// Address to listen on, only clients on this machine can connect by default
+    #[clap(long, default_value = "127.0.0.1")]
+    pub host: IpAddr,
+    /// Milliseconds a client has to make each move before its game is ended
+    #[clap(long, default_value = "5000", value_parser = value_parser!(u64).range(1..))]
+    pub move_time: u64,
+    /// Number of games hosted at once, clients beyond it are turned away
+    #[clap(long, default_value = "64", value_parser = value_parser!(u64).range(1..))]
+    pub max_sessions: u64,
+    /// File the result of every game is appended to as a JSON line, defaults to
+    /// results.jsonl in the data directory
+    #[clap(long)]
+    pub results: Option<PathBuf>,
+}
+
+/// How a session ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum End {
+    /// The game was played until it was lost
+    Finished,
+    /// The client sent `quit`
+    Quit,
+    /// The client closed the connection, or it broke
+    Disconnected,
+    /// The client didn't make a move within the time limit
+    TimedOut,
+    /// The client sent a line longer than the server accepts
+    Oversized,
+}
+
+impl Display for End {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            End::Finished => "finished",
+            End::Quit => "quit",
+            End::Disconnected => "disconnected",
+            End::TimedOut => "timed out",
+            End::Oversized => "sent an oversized request",
+        })
+    }
+}
+
+/// Outcome of one client's game, as written to the results file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SessionResult {
+    pub session: u64,
+    pub client: String,
+    pub seed: u64,
+    pub end: End,
+    pub score: usize,
+    pub moves: usize,
+    pub largest_tile: usize,
+    pub won: bool,
+    pub duration_ms: u64,
+}
+
+/// State shared by the threads of every session
+struct Shared {
+    options: GameOptions,
+    move_time: Duration,
+    max_sessions: usize,
+    active: AtomicUsize,
+    next_session: AtomicU64,
+    results: Mutex<File>,
+}
+
+/// Hosts one game per connection, each on a thread of its own so a client that stalls or
+/// misbehaves only ends its own game
+pub struct Server {
+    listener: TcpListener,
+    shared: Arc<Shared>,
+}
+
+impl Server {
+    /// Listens on the address, sessions play games by the options. With a seed, session
+    /// `n` is seeded with `seed + n` so a competition can be played again, otherwise at random
+    pub fn bind(
+        addr: SocketAddr,
+        options: GameOptions,
+        move_time: Duration,
+        max_sessions: usize,
+        results: File,
+    ) -> io::Result<Self> {
+        options
+            .game()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            shared: Arc::new(Shared {
+                options,
+                move_time,
+                max_sessions,
+                active: AtomicUsize::new(0),
+                next_session: AtomicU64::new(0),
+                results: Mutex::new(results),
+            }),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts clients until the listener fails
+    pub fn run(&self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                // The client gave up before it was accepted, the next one may be fine
+                Err(e) if e.kind() == io::ErrorKind::ConnectionAborted => continue,
+                Err(e) => return Err(e),
+            };
+            if self.shared.active.fetch_add(1, Ordering::SeqCst) >= self.shared.max_sessions {
+                self.shared.active.fetch_sub(1, Ordering::SeqCst);
+                let _ = stream.set_write_timeout(Some(WRITE_TIMEOUT));
+                let _ = engine::send(
+                    &mut &stream,
+                    &Response::error(ErrorCode::Busy, "too many games are being played"),
+                );
+                continue;
+            }
+
+            let shared = Arc::clone(&self.shared);
+            thread::spawn(move || {
+                let id = shared.next_session.fetch_add(1, Ordering::SeqCst);
+                if let Some(result) = host(&stream, id, &shared) {
+                    log(&shared, &result);
+                }
+                shared.active.fetch_sub(1, Ordering::SeqCst);
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Plays a game with the client until it ends, `None` if the game couldn't be started
+fn host(stream: &TcpStream, id: u64, shared: &Shared) -> Option<SessionResult> {
+    let started = Instant::now();
+    let seed = shared
+        .options
+        .seed
+        .map_or_else(rand::random, |seed| seed.wrapping_add(id));
+    let game = GameOptions {
+        seed: Some(seed),
+        ..shared.options.clone()
+    }
+    .game()
+    .expect("the options were checked when the server started");
+    let mut session = Session::restricted(game);
+
+    stream.set_write_timeout(Some(WRITE_TIMEOUT)).ok()?;
+    // Responses are written in pieces, they go out as one packet once flushed
+    let mut writer = BufWriter::new(stream);
+    let mut reader = BufReader::new(stream);
+    let end = match play(&mut session, &mut reader, &mut writer, shared.move_time) {
+        Ok(end) => end,
+        Err(_) => End::Disconnected,
+    };
+    let game = session.game();
+
+    Some(SessionResult {
+        session: id,
+        client: stream
+            .peer_addr()
+            .map_or_else(|_| "unknown".to_string(), |addr| addr.to_string()),
+        seed,
+        end,
+        score: game.score(),
+        moves: game.moves(),
+        largest_tile: game.largest_tile().score(),
+        won: game.has_won(),
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Answers the client's requests until its game is lost or it stops playing by the rules
+fn play(
+    session: &mut Session,
+    reader: &mut BufReader<&TcpStream>,
+    writer: &mut BufWriter<&TcpStream>,
+    move_time: Duration,
+) -> io::Result<End> {
+    engine::send(writer, &session.state())?;
+    let mut line = String::new();
+    // The clock runs from the start of the turn, other requests don't buy time
+    let mut deadline = Instant::now() + move_time;
+    loop {
+        line.clear();
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                return timed_out(writer, move_time);
+            }
+            reader.get_ref().set_read_timeout(Some(left))?;
+            let before = line.len();
+            match reader
+                .by_ref()
+                .take(MAX_LINE + 1 - before as u64)
+                .read_line(&mut line)
+            {
+                Ok(_) if line.ends_with('\n') => break,
+                Ok(_) if line.len() as u64 > MAX_LINE => {
+                    engine::send(
+                        writer,
+                        &Response::error(
+                            ErrorCode::InvalidJson,
+                            format!("requests are at most {MAX_LINE} bytes long"),
+                        ),
+                    )?;
+                    return Ok(End::Oversized);
+                }
+                Ok(0) => return Ok(End::Disconnected),
+                Ok(_) => continue,
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return timed_out(writer, move_time);
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let moves = session.game().moves();
+        let response = session.handle(&line);
+        engine::send(writer, &response)?;
+        if session.game().moves() != moves {
+            deadline = Instant::now() + move_time;
+        }
+        if response == Response::Bye {
+            return Ok(End::Quit);
+        }
+        if let Status::Lost(_) = session.game().status() {
+            return Ok(End::Finished);
+        }
+    }
+}
+
+fn timed_out(writer: &mut BufWriter<&TcpStream>, move_time: Duration) -> io::Result<End> {
+    engine::send(
+        writer,
+        &Response::error(
+            ErrorCode::TimedOut,
+            format!("no move was made within {}ms", move_time.as_millis()),
+        ),
+    )?;
+    Ok(End::TimedOut)
+}
+
+/// Appends the result to the results file and reports it on stdout
+fn log(shared: &Shared, result: &SessionResult) {
+    let mut line = serde_json::to_string(result).expect("results serialize");
+    line.push('\n');
+    let mut file = shared
+        .results
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Err(e) = file.write_all(line.as_bytes()) {
+        eprintln!(
+            "Couldn't record the result of session {}: {e}",
+            result.session
+        );
+    }
+    drop(file);
+
+    println!(
+        "Session {} ({}): {}, {} points in {} moves",
+        result.session, result.client, result.end, result.score, result.moves
+    );
+}
+
+/// Runs the `serve` subcommand until it is interrupted
+pub fn run(opts: &ServeOpts, options: GameOptions) -> io::Result<()> {
+    let path = match &opts.results {
+        Some(path) => path.clone(),
+        None => storage::data_dir()?.join(RESULTS_FILE),
+    };
+    let results = OpenOptions::new().create(true).append(true).open(&path)?;
+    let server = Server::bind(
+        (opts.host, opts.port).into(),
+        options,
+        Duration::from_millis(opts.move_time),
+        opts.max_sessions as usize,
+        results,
+    )?;
+    println!(
+        "Listening on {}, results are appended to {}",
+        server.local_addr()?,
+        path.display()
+    );
+
+    server.run()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, net::Ipv4Addr};
+
+    use twozero48::engine::GameState;
+
+    use super::*;
+
+    fn start(move_time: Duration, max_sessions: usize) -> (SocketAddr, PathBuf) {
+        let path = std::env::temp_dir().join(format!(
+            "twozero48-results-{}-{}.jsonl",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        let options = GameOptions {
+            seed: Some(100),
+            ..Default::default()
+        };
+        let server = Server::bind(
+            (Ipv4Addr::LOCALHOST, 0).into(),
+            options,
+            move_time,
+            max_sessions,
+            File::create(&path).unwrap(),
+        )
+        .unwrap();
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+
+        (addr, path)
+    }
+
+    struct Client {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Client {
+        fn connect(addr: SocketAddr) -> Self {
+            let stream = TcpStream::connect(addr).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(10)))
+                .unwrap();
+            Self {
+                reader: BufReader::new(stream.try_clone().unwrap()),
+                writer: stream,
+            }
+        }
+
+        fn send(&mut self, line: &str) {
+            self.writer
+                .write_all(format!("{line}\n").as_bytes())
+                .unwrap();
+        }
+
+        /// Next response, `None` once the server closed the connection
+        fn receive(&mut self) -> Option<Response> {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line).unwrap() {
+                0 => None,
+                _ => Some(serde_json::from_str(&line).unwrap()),
+            }
+        }
+
+        fn state_after(&mut self, request: &str) -> GameState {
+            self.send(request);
+            self.state()
+        }
+
+        fn state(&mut self) -> GameState {
+            match self.receive() {
+                Some(Response::State(state)) => state,
+                other => panic!("expected a state, got {other:?}"),
+            }
+        }
+    }
+
+    fn results(path: &PathBuf, count: usize) -> Vec<SessionResult> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            let results: Vec<SessionResult> = fs::read_to_string(path)
+                .unwrap()
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect();
+            if results.len() >= count || Instant::now() > deadline {
+                return results;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn sessions_play_games_of_their_own() {
+        let (addr, path) = start(Duration::from_secs(5), 8);
+        let mut clients = [Client::connect(addr), Client::connect(addr)];
+        let seeds: Vec<_> = clients.iter_mut().map(|c| c.state().seed).collect();
+        assert_ne!(seeds[0], seeds[1]);
+
+        // One client plays to the end while the other stays in the middle of its game
+        let mut state = clients[0].state_after(r#"{"cmd": "state"}"#);
+        while let Some(&mov) = state.legal.first() {
+            state = clients[0]
+                .state_after(&format!(r#"{{"cmd": "move", "dir": "{mov:?}"}}"#).to_lowercase());
+        }
+        assert_eq!(state.status, "lost");
+        assert!(clients[0].receive().is_none(), "finished games are closed");
+
+        clients[1].send(r#"{"cmd": "new"}"#);
+        assert!(matches!(
+            clients[1].receive(),
+            Some(Response::Error {
+                code: ErrorCode::Forbidden,
+                ..
+            })
+        ));
+        clients[1].send(r#"{"cmd": "quit"}"#);
+        assert_eq!(clients[1].receive(), Some(Response::Bye));
+
+        let mut results = results(&path, 2);
+        results.sort_by_key(|result| result.end as u8);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].end, End::Finished);
+        assert_eq!(results[0].score, state.score);
+        assert_eq!(results[1].end, End::Quit);
+        assert_eq!(results[1].moves, 0);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn stalled_clients_dont_hold_up_others() {
+        let (addr, path) = start(Duration::from_millis(200), 2);
+        let mut stalled = Client::connect(addr);
+        let mut playing = Client::connect(addr);
+        stalled.state();
+        playing.state();
+
+        // Both slots are taken
+        let mut turned_away = Client::connect(addr);
+        assert!(matches!(
+            turned_away.receive(),
+            Some(Response::Error {
+                code: ErrorCode::Busy,
+                ..
+            })
+        ));
+
+        let mut state = playing.state_after(r#"{"cmd": "state"}"#);
+        for _ in 0..3 {
+            thread::sleep(Duration::from_millis(100));
+            let mov = format!(r#"{{"cmd": "move", "dir": "{:?}"}}"#, state.legal[0]);
+            state = playing.state_after(&mov.to_lowercase());
+        }
+        assert!(matches!(
+            stalled.receive(),
+            Some(Response::Error {
+                code: ErrorCode::TimedOut,
+                ..
+            })
+        ));
+        assert!(stalled.receive().is_none());
+
+        // Exactly one byte too many, so the server reads all of it before closing
+        playing
+            .writer
+            .write_all("x".repeat(MAX_LINE as usize + 1).as_bytes())
+            .unwrap();
+        assert!(matches!(
+            playing.receive(),
+            Some(Response::Error {
+                code: ErrorCode::InvalidJson,
+                ..
+            })
+        ));
+
+        let ends: Vec<End> = results(&path, 2).iter().map(|result| result.end).collect();
+        assert!(ends.contains(&End::TimedOut));
+        assert!(ends.contains(&End::Oversized));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn asking_for_the_state_doesnt_stop_the_clock() {
+        let (addr, path) = start(Duration::from_millis(400), 2);
+        let mut polling = Client::connect(addr);
+        polling.state();
+        let started = Instant::now();
+        for _ in 0..3 {
+            thread::sleep(Duration::from_millis(100));
+            polling.state_after(r#"{"cmd": "state"}"#);
+        }
+        assert!(matches!(
+            polling.receive(),
+            Some(Response::Error {
+                code: ErrorCode::TimedOut,
+                ..
+            })
+        ));
+        // Had the last request restarted the clock, it would run out after 700ms
+        assert!(started.elapsed() < Duration::from_millis(600));
+
+        assert_eq!(results(&path, 1)[0].end, End::TimedOut);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,404 @@
+use std::{
+    collections::BTreeMap,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use twozero48::{
+    Game, Size, Status, Tile,
+    bitboard::{self, BitGame},
+    spawn::{self, SpawnRules},
+    strategy::{self, BUILTIN, Strategy},
+};
+
+/// Options of the `simulate` subcommand
+#[derive(Args)]
+pub struct SimulateOpts {
+    /// Number of games to play
+    #[clap(long, default_value = "1000")]
+    pub games: usize,
+    /// Strategy playing the games: expectimax[:depth], greedy, corner or random
+    #[clap(long, default_value = "expectimax:2")]
+    pub strategy: String,
+    /// Number of threads playing games, defaults to the number of CPUs
+    #[clap(long)]
+    pub threads: Option<usize>,
+    /// Format the statistics are printed in
+    #[clap(long, value_enum, default_value = "table")]
+    pub format: Format,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+/// Outcome of a single simulated game
+#[derive(Debug, PartialEq)]
+struct GameResult {
+    won: bool,
+    score: usize,
+    largest_tile: usize,
+    moves: usize,
+}
+
+impl GameResult {
+    fn of(game: &Game) -> Self {
+        Self {
+            won: matches!(game.status(), Status::Won),
+            score: game.score(),
+            largest_tile: game.largest_tile().score(),
+            moves: game.moves(),
+        }
+    }
+}
+
+/// Plays a game with the seed to its end. 4x4 games with random spawns are played on a
+/// [`BitGame`], which goes back to a [`Game`] if two 32768s have to merge, the result is the
+/// same either way.
+fn play(
+    size: Size,
+    winning: Tile,
+    rules: &SpawnRules,
+    spawner: &str,
+    seed: u64,
+    strategy: &mut dyn Strategy,
+) -> GameResult {
+    let fast = (size == bitboard::SIDE.into() && spawner == "random")
+        .then(|| BitGame::with_rules(winning, rules.clone(), seed))
+        .flatten();
+    let Some(mut fast) = fast else {
+        let mut game = Game::with_rules(size, winning, rules.clone(), seed);
+        game.set_spawner(spawn::builtin(spawner).expect("spawner was validated"));
+        strategy::play_out(&mut game, strategy);
+        return GameResult::of(&game);
+    };
+
+    if let Some(mov) = strategy::play_out_bits(&mut fast, strategy) {
+        let mut game = fast.to_game();
+        game.mover(mov);
+        game.refresh();
+        strategy::play_out(&mut game, strategy);
+        return GameResult::of(&game);
+    }
+
+    GameResult {
+        won: matches!(fast.status(), Status::Won),
+        score: fast.score(),
+        largest_tile: fast.board().largest_tile().score(),
+        moves: fast.moves(),
+    }
+}
+
+/// Spread of a statistic over all games
+#[derive(Serialize)]
+pub struct Summary {
+    mean: f64,
+    min: usize,
+    p10: usize,
+    p25: usize,
+    median: usize,
+    p75: usize,
+    p90: usize,
+    p99: usize,
+    max: usize,
+}
+
+impl Summary {
+    fn of(mut values: Vec<usize>) -> Self {
+        values.sort_unstable();
+        let mean = values.iter().sum::<usize>() as f64 / values.len().max(1) as f64;
+        Self {
+            mean,
+            min: percentile(&values, 0.0),
+            p10: percentile(&values, 10.0),
+            p25: percentile(&values, 25.0),
+            median: percentile(&values, 50.0),
+            p75: percentile(&values, 75.0),
+            p90: percentile(&values, 90.0),
+            p99: percentile(&values, 99.0),
+            max: percentile(&values, 100.0),
+        }
+    }
+
+    fn columns(&self) -> [(&'static str, String); 9] {
+        [
+            ("mean", format!("{:.1}", self.mean)),
+            ("min", self.min.to_string()),
+            ("p10", self.p10.to_string()),
+            ("p25", self.p25.to_string()),
+            ("median", self.median.to_string()),
+            ("p75", self.p75.to_string()),
+            ("p90", self.p90.to_string()),
+            ("p99", self.p99.to_string()),
+            ("max", self.max.to_string()),
+        ]
+    }
+}
+
+/// Nearest-rank percentile of sorted values, 0 if there are none
+fn percentile(sorted: &[usize], p: f64) -> usize {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Aggregate statistics over every simulated game
+#[derive(Serialize)]
+pub struct Report {
+    strategy: String,
+    width: usize,
+    height: usize,
+    winning: usize,
+    spawn: String,
+    spawner: String,
+    seed: u64,
+    games: usize,
+    wins: usize,
+    win_rate: f64,
+    score: Summary,
+    moves: Summary,
+    /// Number of games that ended with each largest tile
+    largest_tiles: BTreeMap<usize, usize>,
+}
+
+/// Plays every game with [`play`], game `i` is seeded with `seed + i` so the results don't
+/// depend on the number of threads.
+pub fn run(
+    opts: &SimulateOpts,
+    size: Size,
+    winning: Tile,
+    rules: &SpawnRules,
+    spawner: &str,
+    seed: u64,
+) -> Result<Report, String> {
+    if spawn::builtin(spawner).is_none() {
+        return Err(format!(
+            "{spawner} is not a known spawner, use one of: {}",
+            spawn::BUILTIN.join(", ")
+        ));
+    }
+    if strategy::builtin(&opts.strategy, seed, rules).is_none() {
+        return Err(format!(
+            "{} is not a known strategy, use one of: {}",
+            opts.strategy,
+            BUILTIN.join(", ")
+        ));
+    }
+    let threads = opts
+        .threads
+        .or_else(|| thread::available_parallelism().ok().map(usize::from))
+        .unwrap_or(1)
+        .clamp(1, opts.games.max(1));
+
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, GameResult)> = thread::scope(|s| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut results = vec![];
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        if i >= opts.games {
+                            return results;
+                        }
+                        let game_seed = seed.wrapping_add(i as u64);
+                        let mut strategy = strategy::builtin(&opts.strategy, game_seed, rules)
+                            .expect("strategy was validated");
+                        results.push((
+                            i,
+                            play(size, winning, rules, spawner, game_seed, strategy.as_mut()),
+                        ));
+                    }
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("simulation thread panicked"))
+            .collect()
+    });
+    results.sort_by_key(|(i, _)| *i);
+    let results: Vec<GameResult> = results.into_iter().map(|(_, result)| result).collect();
+
+    let wins = results.iter().filter(|r| r.won).count();
+    let mut largest_tiles = BTreeMap::new();
+    for result in &results {
+        *largest_tiles.entry(result.largest_tile).or_default() += 1;
+    }
+
+    Ok(Report {
+        strategy: opts.strategy.clone(),
+        width: size.width,
+        height: size.height,
+        winning: winning.score(),
+        spawn: rules.to_string(),
+        spawner: spawner.to_string(),
+        seed,
+        games: results.len(),
+        wins,
+        win_rate: wins as f64 / results.len().max(1) as f64,
+        score: Summary::of(results.iter().map(|r| r.score).collect()),
+        moves: Summary::of(results.iter().map(|r| r.moves).collect()),
+        largest_tiles,
+    })
+}
+
+pub fn print(report: &Report, format: Format) -> Result<(), serde_json::Error> {
+    match format {
+        Format::Table => print_table(report),
+        Format::Json => println!("{}", serde_json::to_string_pretty(report)?),
+        Format::Csv => print_csv(report),
+    }
+
+    Ok(())
+}
+
+fn print_table(report: &Report) {
+    println!(
+        "{} games of {} on a {}x{} board to {}, seed {}",
+        report.games, report.strategy, report.width, report.height, report.winning, report.seed
+    );
+    println!(
+        "Spawns    {}, placed by the {} spawner",
+        report.spawn, report.spawner
+    );
+    println!(
+        "Win rate  {:.1}% ({}/{})",
+        report.win_rate * 100.0,
+        report.wins,
+        report.games
+    );
+    println!();
+
+    print!("{:<8}", "");
+    for (name, _) in report.score.columns() {
+        print!("{name:>10}");
+    }
+    println!();
+    for (name, summary) in [("Score", &report.score), ("Moves", &report.moves)] {
+        print!("{name:<8}");
+        for (_, value) in summary.columns() {
+            print!("{value:>10}");
+        }
+        println!();
+    }
+    println!();
+
+    println!("{:>12}  {:>8}  {:>6}", "Largest tile", "Games", "Share");
+    for (tile, count) in report.largest_tiles.iter().rev() {
+        println!(
+            "{tile:>12}  {count:>8}  {:>5.1}%",
+            *count as f64 * 100.0 / report.games.max(1) as f64
+        );
+    }
+}
+
+fn print_csv(report: &Report) {
+    println!("metric,value");
+    println!("strategy,{}", report.strategy);
+    println!("width,{}", report.width);
+    println!("height,{}", report.height);
+    println!("winning,{}", report.winning);
+    println!("spawn,\"{}\"", report.spawn);
+    println!("spawner,{}", report.spawner);
+    println!("seed,{}", report.seed);
+    println!("games,{}", report.games);
+    println!("wins,{}", report.wins);
+    println!("win_rate,{}", report.win_rate);
+    for (prefix, summary) in [("score", &report.score), ("moves", &report.moves)] {
+        for (name, value) in summary.columns() {
+            println!("{prefix}_{name},{value}");
+        }
+    }
+    for (tile, count) in &report.largest_tiles {
+        println!("largest_tile_{tile},{count}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts(threads: usize) -> SimulateOpts {
+        SimulateOpts {
+            games: 20,
+            strategy: "corner".to_string(),
+            threads: Some(threads),
+            format: Format::Table,
+        }
+    }
+
+    #[test]
+    fn nearest_rank_percentiles() {
+        let values: Vec<usize> = (1..=10).collect();
+        assert_eq!(percentile(&values, 0.0), 1);
+        assert_eq!(percentile(&values, 50.0), 5);
+        assert_eq!(percentile(&values, 90.0), 9);
+        assert_eq!(percentile(&values, 100.0), 10);
+        assert_eq!(percentile(&[], 50.0), 0);
+    }
+
+    fn run_standard(opts: &SimulateOpts, spawner: &str, seed: u64) -> Result<Report, String> {
+        let rules = SpawnRules::default();
+        run(
+            opts,
+            4.into(),
+            Tile::TWO_THOUSAND_FORTY_EIGHT,
+            &rules,
+            spawner,
+            seed,
+        )
+    }
+
+    #[test]
+    fn results_do_not_depend_on_threads() {
+        let one = run_standard(&opts(1), "random", 9).unwrap();
+        let four = run_standard(&opts(4), "random", 9).unwrap();
+        assert_eq!(one.games, 20);
+        assert_eq!(
+            serde_json::to_string(&one).unwrap(),
+            serde_json::to_string(&four).unwrap()
+        );
+    }
+
+    #[test]
+    fn bit_games_play_out_like_games() {
+        let rules = SpawnRules::default();
+        for name in ["expectimax:1", "corner", "greedy", "random"] {
+            for seed in 0..5 {
+                let mut game =
+                    Game::with_rules(4, Tile::TWO_THOUSAND_FORTY_EIGHT, rules.clone(), seed);
+                let mut strategy = strategy::builtin(name, seed, &rules).unwrap();
+                strategy::play_out(&mut game, strategy.as_mut());
+
+                let mut strategy = strategy::builtin(name, seed, &rules).unwrap();
+                let fast = play(
+                    4.into(),
+                    Tile::TWO_THOUSAND_FORTY_EIGHT,
+                    &rules,
+                    "random",
+                    seed,
+                    strategy.as_mut(),
+                );
+                assert_eq!(fast, GameResult::of(&game));
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_names_are_rejected() {
+        assert!(run_standard(&opts(1), "psychic", 0).is_err());
+
+        let mut opts = opts(1);
+        opts.strategy = "psychic".to_string();
+        assert!(run_standard(&opts, "random", 0).is_err());
+    }
+}
@@ -0,0 +1,530 @@
+//! Expectimax search over [`Game`](crate::Game) states, the player picks the [`Move`] with
+//! the best outcome (max nodes) while spawns are averaged over every empty cell (chance nodes)
+//! and every tile the [`SpawnRules`] spawn, as many times as they spawn after a move.
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Board, Cell, Move, Tile,
+    bitboard::{Bitboard, Overflow, SIDE},
+    slide,
+    spawn::SpawnRules,
+};
+
+/// Branches less likely than this are evaluated with the heuristic instead of searched
+const PROBABILITY_CUTOFF: f64 = 1e-4;
+
+/// Evaluates how promising a board is for the player, higher is better
+pub trait Heuristic {
+    fn evaluate(&self, board: &Board) -> f64;
+
+    /// Same as [`evaluate`](Self::evaluate) for a packed 4x4 board
+    fn evaluate_bits(&self, board: Bitboard) -> f64 {
+        self.evaluate(&board.to_board())
+    }
+}
+
+impl<F: Fn(&Board) -> f64> Heuristic for F {
+    fn evaluate(&self, board: &Board) -> f64 {
+        self(board)
+    }
+}
+
+/// Rewards empty cells and possible merges, penalizes rows and columns that aren't
+/// monotonic along with large tiles that are scattered across the board.
+#[derive(Clone, Copy, Default)]
+pub struct DefaultHeuristic;
+
+impl DefaultHeuristic {
+    const LOST_PENALTY: f64 = 200_000.0;
+    const MONOTONICITY_POWER: i32 = 4;
+    const MONOTONICITY_WEIGHT: f64 = 47.0;
+    const SUM_POWER: f64 = 3.5;
+    const SUM_WEIGHT: f64 = 11.0;
+    const MERGES_WEIGHT: f64 = 700.0;
+    const EMPTY_WEIGHT: f64 = 270.0;
+
+    fn line(&self, line: impl Iterator<Item = Tile>) -> f64 {
+        // Blocked cells are left out, the tiles on either side are judged as neighbours
+        let ranks: Vec<f64> = line
+            .filter(|tile| !tile.is_blocked())
+            .map(|tile| tile.exponent() as f64)
+            .collect();
+
+        let mut sum = 0.0;
+        let mut empty = 0.0;
+        let mut merges = 0.0;
+        let mut prev = 0.0;
+        let mut counter = 0.0;
+        for &rank in &ranks {
+            sum += rank.powf(Self::SUM_POWER);
+            if rank == 0.0 {
+                empty += 1.0;
+                continue;
+            }
+            if prev == rank {
+                counter += 1.0;
+            } else if counter > 0.0 {
+                merges += 1.0 + counter;
+                counter = 0.0;
+            }
+            prev = rank;
+        }
+        if counter > 0.0 {
+            merges += 1.0 + counter;
+        }
+
+        let (mut left, mut right) = (0.0, 0.0);
+        for pair in ranks.windows(2) {
+            let (a, b) = (
+                pair[0].powi(Self::MONOTONICITY_POWER),
+                pair[1].powi(Self::MONOTONICITY_POWER),
+            );
+            if a > b {
+                left += a - b;
+            } else {
+                right += b - a;
+            }
+        }
+
+        Self::LOST_PENALTY + Self::EMPTY_WEIGHT * empty + Self::MERGES_WEIGHT * merges
+            - Self::MONOTONICITY_WEIGHT * f64::min(left, right)
+            - Self::SUM_WEIGHT * sum
+    }
+}
+
+impl Heuristic for DefaultHeuristic {
+    fn evaluate(&self, board: &Board) -> f64 {
+        let rows: f64 = board.iter().map(|row| self.line(row.iter().copied())).sum();
+        let columns: f64 = (0..board[0].len())
+            .map(|i| self.line(board.iter().map(|row| row[i])))
+            .sum();
+
+        rows + columns
+    }
+
+    /// Looks every row and column up in a table of the values of all 4 cell lines
+    fn evaluate_bits(&self, board: Bitboard) -> f64 {
+        static LINES: OnceLock<Vec<f64>> = OnceLock::new();
+        let lines = LINES.get_or_init(|| {
+            (0..1 << 16)
+                .map(|row: usize| self.line((0..SIDE).map(|c| Tile((row >> (4 * c)) as u8 & 0xF))))
+                .collect()
+        });
+        let rows: f64 = board.rows().iter().map(|&row| lines[row as usize]).sum();
+        let columns: f64 = board
+            .transpose()
+            .rows()
+            .iter()
+            .map(|&row| lines[row as usize])
+            .sum();
+
+        rows + columns
+    }
+}
+
+/// The move chosen by a search, along with the expected heuristic value of playing it
+#[derive(Clone, Copy, Debug)]
+pub struct Decision {
+    pub mov: Move,
+    pub value: f64,
+}
+
+/// Expectimax search, looking `depth` moves ahead
+pub struct Expectimax<H = DefaultHeuristic> {
+    depth: u32,
+    heuristic: H,
+    rules: SpawnRules,
+}
+
+impl Expectimax {
+    /// Constructs a search using the [`DefaultHeuristic`]
+    pub fn new(depth: u32) -> Self {
+        Self::with_heuristic(depth, DefaultHeuristic)
+    }
+}
+
+impl Default for Expectimax {
+    fn default() -> Self {
+        Self::new(3)
+    }
+}
+
+impl<H: Heuristic> Expectimax<H> {
+    /// Constructs a search evaluating the leaves with the provided heuristic
+    pub fn with_heuristic(depth: u32, heuristic: H) -> Self {
+        Self {
+            depth: depth.max(1),
+            heuristic,
+            rules: SpawnRules::default(),
+        }
+    }
+
+    /// Averages spawns over the rules of the game searched, instead of the standard ones
+    pub fn set_spawn_rules(&mut self, rules: SpawnRules) {
+        self.rules = rules;
+    }
+
+    /// Returns the best move for the board, `None` if no move changes it
+    pub fn decide(&self, board: &Board) -> Option<Decision> {
+        // Boards merge any tiles, their searches never overflow
+        self.search(board, self.depth, None).ok().flatten()
+    }
+
+    /// Same as [`Expectimax::decide`] for a packed 4x4 board. If the search comes across two
+    /// 32768s that would merge, it is done again on the unpacked board
+    pub fn decide_bits(&self, board: Bitboard) -> Option<Decision> {
+        self.search(&board, self.depth, None)
+            .unwrap_or_else(|Overflow| self.decide(&board.to_board()))
+    }
+
+    /// Like [`Expectimax::decide`], but deepens the search one move at a time and
+    /// settles for the deepest search completed once the time budget runs out.
+    pub fn decide_within(&self, board: &Board, budget: Duration) -> Option<Decision> {
+        let deadline = Instant::now() + budget;
+        let mut decision = self.search(board, 1, None).ok().flatten();
+        for depth in 2..=self.depth {
+            match self.search(board, depth, Some(deadline)).ok().flatten() {
+                Some(deeper) => decision = Some(deeper),
+                None => break,
+            }
+        }
+
+        decision
+    }
+
+    /// Searches `depth` moves ahead, `None` if no move changes the board or the deadline passed.
+    /// [`Overflow`] if a bitboard would have to merge two 32768s along the way
+    fn search<P: Position>(
+        &self,
+        board: &P,
+        depth: u32,
+        deadline: Option<Instant>,
+    ) -> Result<Option<Decision>, Overflow> {
+        let total: f64 = self.rules.weights().iter().map(|(_, weight)| weight).sum();
+        let mut search = Search {
+            heuristic: &self.heuristic,
+            spawns: self
+                .rules
+                .weights()
+                .iter()
+                .map(|&(tile, weight)| (tile, weight / total))
+                .collect(),
+            count: self.rules.count(),
+            cache: HashMap::new(),
+            deadline,
+            timed_out: false,
+            overflowed: false,
+        };
+
+        let decision = Move::ALL
+            .into_iter()
+            .filter_map(|mov| {
+                let next = search.moved(board, mov)?;
+                Some(Decision {
+                    mov,
+                    value: search.chance(&next, depth - 1, 1.0),
+                })
+            })
+            .max_by(|a, b| a.value.total_cmp(&b.value));
+
+        if search.overflowed {
+            Err(Overflow)
+        } else if search.timed_out {
+            Ok(None)
+        } else {
+            Ok(decision)
+        }
+    }
+}
+
+/// A board the search plays on, either a [`Board`] or a [`Bitboard`]
+trait Position: Clone + Eq + Hash {
+    /// The position after the move, `None` if the move doesn't change it
+    fn moved(&self, mov: Move) -> Option<Self>;
+
+    /// Whether the move would merge two 32768s, which only a [`Board`] can hold
+    fn overflows(&self, _mov: Move) -> bool {
+        false
+    }
+
+    /// Empty cells in reading order
+    fn empty_cells(&self) -> Vec<Cell>;
+
+    fn set(&mut self, cell: Cell, tile: Tile);
+
+    fn evaluate<H: Heuristic>(&self, heuristic: &H) -> f64;
+}
+
+impl Position for Board {
+    fn moved(&self, mov: Move) -> Option<Self> {
+        let mut next = self.clone();
+        slide(&mut next, mov);
+        (&next != self).then_some(next)
+    }
+
+    fn empty_cells(&self) -> Vec<Cell> {
+        self.iter()
+            .enumerate()
+            .flat_map(|(r, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, tile)| **tile == Tile::EMPTY)
+                    .map(move |(c, _)| (r, c))
+            })
+            .collect()
+    }
+
+    fn set(&mut self, (r, c): Cell, tile: Tile) {
+        self[r][c] = tile;
+    }
+
+    fn evaluate<H: Heuristic>(&self, heuristic: &H) -> f64 {
+        heuristic.evaluate(self)
+    }
+}
+
+impl Position for Bitboard {
+    fn moved(&self, mov: Move) -> Option<Self> {
+        let (next, _) = self.slide(mov);
+        (next != *self).then_some(next)
+    }
+
+    fn overflows(&self, mov: Move) -> bool {
+        Bitboard::overflows(*self, mov)
+    }
+
+    fn empty_cells(&self) -> Vec<Cell> {
+        Bitboard::empty_cells(*self).collect()
+    }
+
+    fn set(&mut self, cell: Cell, tile: Tile) {
+        Bitboard::set(self, cell, tile);
+    }
+
+    fn evaluate<H: Heuristic>(&self, heuristic: &H) -> f64 {
+        heuristic.evaluate_bits(*self)
+    }
+}
+
+/// State of a single search, caching the value of boards already evaluated
+struct Search<'a, H, P> {
+    heuristic: &'a H,
+    /// Tiles that spawn along with their probability
+    spawns: Vec<(Tile, f64)>,
+    /// Number of tiles spawned after every move
+    count: usize,
+    cache: HashMap<P, (u32, f64)>,
+    deadline: Option<Instant>,
+    timed_out: bool,
+    /// A move would have merged two 32768s, the search is worthless
+    overflowed: bool,
+}
+
+impl<H: Heuristic, P: Position> Search<'_, H, P> {
+    /// The board after the move, `None` if it doesn't change
+    fn moved(&mut self, board: &P, mov: Move) -> Option<P> {
+        if board.overflows(mov) {
+            self.overflowed = true;
+        }
+        board.moved(mov)
+    }
+
+    /// Value of the best move on the board, 0 if the player is locked
+    fn max(&mut self, board: &P, depth: u32, probability: f64) -> f64 {
+        Move::ALL
+            .into_iter()
+            .filter_map(|mov| {
+                let next = self.moved(board, mov)?;
+                Some(self.chance(&next, depth, probability))
+            })
+            .fold(0.0, f64::max)
+    }
+
+    /// Expected value over every possible spawn on the board
+    fn chance(&mut self, board: &P, depth: u32, probability: f64) -> f64 {
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.timed_out = true;
+        }
+        if depth == 0 || probability < PROBABILITY_CUTOFF || self.timed_out || self.overflowed {
+            return board.evaluate(self.heuristic);
+        }
+        if let Some(&(cached_depth, value)) = self.cache.get(board)
+            && cached_depth >= depth
+        {
+            return value;
+        }
+
+        let value = self.spawn(board, depth, probability, self.count);
+
+        self.cache.insert(board.clone(), (depth, value));
+        value
+    }
+
+    /// Expected value over the `left` tiles still to spawn, each on any empty cell
+    fn spawn(&mut self, board: &P, depth: u32, probability: f64, left: usize) -> f64 {
+        let empty = board.empty_cells();
+        if left == 0 || empty.is_empty() {
+            return self.max(board, depth - 1, probability);
+        }
+
+        let cell_probability = probability / empty.len() as f64;
+        let mut next = board.clone();
+        let mut total = 0.0;
+        for cell in empty.iter().copied() {
+            for i in 0..self.spawns.len() {
+                let (tile, tile_probability) = self.spawns[i];
+                next.set(cell, tile);
+                total += tile_probability
+                    * self.spawn(&next, depth, cell_probability * tile_probability, left - 1);
+            }
+            next.set(cell, Tile::EMPTY);
+        }
+
+        total / empty.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Game, Status};
+
+    #[test]
+    fn no_decision_when_locked() {
+        let board = vec![vec![Tile::TWO, Tile::FOUR], vec![Tile::FOUR, Tile::TWO]];
+        assert!(Expectimax::new(2).decide(&board).is_none());
+    }
+
+    #[test]
+    fn takes_the_only_move() {
+        let board = vec![vec![Tile::EMPTY, Tile::TWO], vec![Tile::EMPTY, Tile::FOUR]];
+        let decision = Expectimax::new(2).decide(&board).unwrap();
+        assert_eq!(decision.mov, Move::Left);
+    }
+
+    #[test]
+    fn pluggable_heuristic() {
+        // Prefers boards with the largest tile in the top left corner
+        let corner = |board: &Board| board[0][0].exponent() as f64;
+        let board = vec![
+            vec![Tile::EMPTY, Tile::EIGHT],
+            vec![Tile::EMPTY, Tile::EMPTY],
+        ];
+        let decision = Expectimax::with_heuristic(1, corner)
+            .decide(&board)
+            .unwrap();
+        assert_eq!(decision.mov, Move::Left);
+        assert_eq!(decision.value, Tile::EIGHT.exponent() as f64);
+    }
+
+    #[test]
+    fn spawns_follow_the_rules() {
+        // Slides and merges keep the sum of the tiles, so it only grows by what spawns
+        let sum = |board: &Board| {
+            board
+                .iter()
+                .flatten()
+                .map(|tile| tile.score())
+                .sum::<usize>() as f64
+        };
+        let board = vec![vec![Tile::EMPTY, Tile::TWO], vec![Tile::EMPTY, Tile::EMPTY]];
+
+        let standard = Expectimax::with_heuristic(2, sum).decide(&board).unwrap();
+        assert!((standard.value - (2.0 + 0.9 * 2.0 + 0.1 * 4.0)).abs() < 1e-9);
+
+        let mut eights = Expectimax::with_heuristic(2, sum);
+        eights.set_spawn_rules(SpawnRules::new(vec![(Tile::EIGHT, 3.0)], 2, 2).unwrap());
+        let decision = eights.decide(&board).unwrap();
+        assert!((decision.value - (2.0 + 2.0 * 8.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn settles_for_a_shallow_search_when_out_of_time() {
+        let board = vec![
+            vec![Tile::TWO, Tile::EMPTY, Tile::EMPTY, Tile::FOUR],
+            vec![Tile::EMPTY, Tile::EIGHT, Tile::EMPTY, Tile::EMPTY],
+            vec![Tile::EMPTY, Tile::EMPTY, Tile::TWO, Tile::EMPTY],
+            vec![Tile::SIXTEEN, Tile::EMPTY, Tile::EMPTY, Tile::EMPTY],
+        ];
+        let solver = Expectimax::new(8);
+        let decision = solver.decide_within(&board, Duration::ZERO).unwrap();
+        let shallow = Expectimax::new(1).decide(&board).unwrap();
+        assert_eq!(decision.mov, shallow.mov);
+    }
+
+    #[test]
+    fn plays_past_512() {
+        let solver = Expectimax::new(2);
+        let mut game = Game::with_seed(4, Tile::FIVE_HUNDRED_TWELVE, 2048);
+        while let Status::On = game.status() {
+            let decision = solver.decide(game.board()).unwrap();
+            assert!(game.mover(decision.mov));
+            game.refresh();
+        }
+        assert!(matches!(game.status(), Status::Won));
+    }
+
+    #[test]
+    fn bitboards_are_searched_like_boards() {
+        let solver = Expectimax::new(2);
+        let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 7);
+        for _ in 0..40 {
+            let packed = Bitboard::from_board(game.board()).unwrap();
+            let Some(decision) = solver.decide(game.board()) else {
+                break;
+            };
+            let fast = solver.decide_bits(packed).unwrap();
+            assert_eq!((fast.mov, fast.value), (decision.mov, decision.value));
+            assert!(game.mover(decision.mov));
+            game.refresh();
+        }
+
+        // Only a board can merge the 32768s, so the bitboard search falls back to one
+        let board: Board = [
+            [32768, 32768, 2, 4],
+            [2, 4, 8, 16],
+            [4, 8, 16, 32],
+            [8, 16, 32, 64],
+        ]
+        .iter()
+        .map(|row| row.iter().map(|&v| Tile::from_score(v).unwrap()).collect())
+        .collect();
+        let packed = Bitboard::from_board(&board).unwrap();
+        assert!(matches!(solver.search(&packed, 2, None), Err(Overflow)));
+        let (decision, fast) = (
+            solver.decide(&board).unwrap(),
+            solver.decide_bits(packed).unwrap(),
+        );
+        assert_eq!((fast.mov, fast.value), (decision.mov, decision.value));
+    }
+
+    #[test]
+    #[ignore = "plays full games, run with --release -- --ignored"]
+    fn routinely_reaches_2048() {
+        let solver = Expectimax::default();
+        let games = 10;
+        let won = (0..games)
+            .filter(|&seed| {
+                let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, seed);
+                while let Status::On = game.status() {
+                    let Some(decision) = solver.decide(game.board()) else {
+                        break;
+                    };
+                    game.mover(decision.mov);
+                    game.refresh();
+                }
+                matches!(game.status(), Status::Won)
+            })
+            .count();
+        assert!(won >= 8, "only {won} of {games} games reached 2048");
+    }
+}
@@ -0,0 +1,419 @@
+//! Rules for the tiles that appear on the board, at the start and after every move,
+//! and the [`Spawner`]s that decide where they go.
+
+use std::fmt::Display;
+
+use rand::{Rng, RngExt};
+
+use crate::{
+    Board, Cell, Spawn, Tile, legal_moves, slide,
+    solver::{DefaultHeuristic, Heuristic},
+};
+
+/// Names of the spawners that can be built with [`builtin`]
+pub const BUILTIN: [&str; 3] = ["random", "evil", "friendly"];
+
+/// Which tiles spawn and how many of them, the [`Default`] is standard 2048:
+/// two tiles to start with, then one per move that is a 2 (90%) or a 4 (10%)
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpawnRules {
+    weights: Vec<(Tile, f64)>,
+    count: usize,
+    start_tiles: usize,
+}
+
+impl Default for SpawnRules {
+    fn default() -> Self {
+        Self {
+            weights: vec![(Tile::TWO, 0.9), (Tile::FOUR, 0.1)],
+            count: 1,
+            start_tiles: 2,
+        }
+    }
+}
+
+impl SpawnRules {
+    /// Tiles are picked in proportion to their weights, which don't need to add up to 1.
+    /// At least one tile has to spawn per move.
+    pub fn new(
+        weights: Vec<(Tile, f64)>,
+        count: usize,
+        start_tiles: usize,
+    ) -> Result<Self, String> {
+        if weights.is_empty() {
+            return Err("at least one tile has to be able to spawn".to_string());
+        }
+        for &(tile, weight) in &weights {
+            if tile == Tile::EMPTY || tile.is_blocked() {
+                return Err("empty and blocked cells can't spawn".to_string());
+            }
+            if !weight.is_finite() || weight <= 0.0 {
+                return Err(format!(
+                    "{tile} has weight {weight}, weights must be positive"
+                ));
+            }
+        }
+        if count == 0 {
+            return Err("at least one tile has to spawn per move".to_string());
+        }
+
+        Ok(Self {
+            weights,
+            count,
+            start_tiles,
+        })
+    }
+
+    /// Tiles that can spawn, with their weights
+    pub fn weights(&self) -> &[(Tile, f64)] {
+        &self.weights
+    }
+
+    /// Number of tiles spawned after every move
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Number of tiles on the board when the game starts
+    pub fn start_tiles(&self) -> usize {
+        self.start_tiles
+    }
+
+    /// Chance of the tile being picked, 0 if it never spawns
+    pub fn probability(&self, tile: Tile) -> f64 {
+        let total: f64 = self.weights.iter().map(|(_, weight)| weight).sum();
+        self.weights
+            .iter()
+            .filter(|(spawned, _)| *spawned == tile)
+            .map(|(_, weight)| weight / total)
+            .sum()
+    }
+
+    /// Picks the value of a spawned tile
+    pub fn pick<R: Rng + ?Sized>(&self, rng: &mut R) -> Tile {
+        let total: f64 = self.weights.iter().map(|(_, weight)| weight).sum();
+        let mut left = rng.random_range(0.0..total);
+        for &(tile, weight) in &self.weights {
+            if left < weight {
+                return tile;
+            }
+            left -= weight;
+        }
+
+        // Rounding can leave a sliver of the range past the last weight
+        self.weights[self.weights.len() - 1].0
+    }
+}
+
+/// Parses weights written as `value:weight` pairs, e.g. `2:0.9,4:0.1`
+pub fn parse_weights(weights: &str) -> Result<Vec<(Tile, f64)>, String> {
+    weights
+        .split(',')
+        .map(|pair| {
+            let (value, weight) = pair
+                .split_once(':')
+                .ok_or_else(|| format!("{pair} is not a value:weight pair, e.g. 2:0.9"))?;
+            let tile = value
+                .trim()
+                .parse()
+                .ok()
+                .and_then(Tile::from_score)
+                .filter(|&tile| tile != Tile::EMPTY)
+                .ok_or_else(|| format!("{value} is not a tile value, e.g. 2 or 4"))?;
+            let weight = weight
+                .trim()
+                .parse()
+                .map_err(|_| format!("{weight} is not a weight, e.g. 0.9"))?;
+
+            Ok((tile, weight))
+        })
+        .collect()
+}
+
+/// Decides where the next tile spawns and which one it is
+pub trait Spawner {
+    /// Name the spawner is saved by, see [`builtin`]
+    fn name(&self) -> &str;
+
+    /// Picks an empty cell and a tile allowed by the rules, `None` if the board is full
+    fn spawn(&mut self, board: &Board, rules: &SpawnRules, rng: &mut dyn Rng) -> Option<Spawn>;
+
+    /// Called when an undo or redo takes the game back to when `spawned` tiles had been
+    /// spawned, so spawners that keep track of their spawns can pick up from there
+    fn rewind(&mut self, _spawned: usize) {}
+}
+
+/// Returns the empty cells of the board as `(row, column)`
+fn empty_cells(board: &Board) -> Vec<(usize, usize)> {
+    board
+        .iter()
+        .enumerate()
+        .flat_map(|(r, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, tile)| **tile == Tile::EMPTY)
+                .map(move |(c, _)| (r, c))
+        })
+        .collect()
+}
+
+/// Spawns on a uniformly random empty cell, standard 2048
+pub struct RandomSpawner;
+
+impl Spawner for RandomSpawner {
+    fn name(&self) -> &str {
+        "random"
+    }
+
+    fn spawn(&mut self, board: &Board, rules: &SpawnRules, rng: &mut dyn Rng) -> Option<Spawn> {
+        let empty = empty_cells(board);
+        if empty.is_empty() {
+            return None;
+        }
+        let at = empty[rng.random_range(0..empty.len())];
+
+        Some(Spawn {
+            at,
+            tile: rules.pick(rng),
+        })
+    }
+}
+
+/// Spawns a fixed sequence of tiles regardless of the rules, then none at all.
+/// A tile goes to its cell if that is empty, otherwise to the first empty cell
+/// in reading order, so the sequence plays out the same for the same moves.
+pub struct SequenceSpawner {
+    tiles: Vec<(Tile, Option<Cell>)>,
+    next: usize,
+}
+
+impl SequenceSpawner {
+    pub fn new(tiles: Vec<(Tile, Option<Cell>)>) -> Self {
+        Self { tiles, next: 0 }
+    }
+}
+
+impl Spawner for SequenceSpawner {
+    fn name(&self) -> &str {
+        "sequence"
+    }
+
+    fn spawn(&mut self, board: &Board, _: &SpawnRules, _: &mut dyn Rng) -> Option<Spawn> {
+        let &(tile, cell) = self.tiles.get(self.next)?;
+        let at = cell
+            .filter(|&(r, c)| board.get(r).and_then(|row| row.get(c)) == Some(&Tile::EMPTY))
+            .or_else(|| empty_cells(board).first().copied())?;
+        self.next += 1;
+
+        Some(Spawn { at, tile })
+    }
+
+    fn rewind(&mut self, spawned: usize) {
+        self.next = spawned;
+    }
+}
+
+/// Every spawn the rules allow, with the best evaluation the player can reach
+/// with their next move. A spawn that leaves no move is worth negative infinity.
+fn rank_spawns<H: Heuristic>(
+    board: &Board,
+    rules: &SpawnRules,
+    heuristic: &H,
+) -> impl Iterator<Item = (Spawn, f64)> {
+    let tiles: Vec<Tile> = rules.weights().iter().map(|&(tile, _)| tile).collect();
+    empty_cells(board).into_iter().flat_map(move |at| {
+        tiles
+            .iter()
+            .map(|&tile| {
+                let mut spawned = board.clone();
+                spawned[at.0][at.1] = tile;
+                let best = legal_moves(&spawned)
+                    .map(|mov| {
+                        let mut next = spawned.clone();
+                        slide(&mut next, mov);
+                        heuristic.evaluate(&next)
+                    })
+                    .fold(f64::NEG_INFINITY, f64::max);
+
+                (Spawn { at, tile }, best)
+            })
+            .collect::<Vec<_>>()
+    })
+}
+
+/// Spawns the tile that leaves the player the worst best move, by the heuristic
+pub struct EvilSpawner<H = DefaultHeuristic>(pub H);
+
+impl<H: Heuristic> Spawner for EvilSpawner<H> {
+    fn name(&self) -> &str {
+        "evil"
+    }
+
+    fn spawn(&mut self, board: &Board, rules: &SpawnRules, _: &mut dyn Rng) -> Option<Spawn> {
+        rank_spawns(board, rules, &self.0)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(spawn, _)| spawn)
+    }
+}
+
+/// Spawns the tile that leaves the player the best move, by the heuristic
+pub struct FriendlySpawner<H = DefaultHeuristic>(pub H);
+
+impl<H: Heuristic> Spawner for FriendlySpawner<H> {
+    fn name(&self) -> &str {
+        "friendly"
+    }
+
+    fn spawn(&mut self, board: &Board, rules: &SpawnRules, _: &mut dyn Rng) -> Option<Spawn> {
+        rank_spawns(board, rules, &self.0)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(spawn, _)| spawn)
+    }
+}
+
+/// Builds one of the [`BUILTIN`] spawners by name
+pub fn builtin(name: &str) -> Option<Box<dyn Spawner + Send>> {
+    match name {
+        "random" => Some(Box::new(RandomSpawner)),
+        "evil" => Some(Box::new(EvilSpawner(DefaultHeuristic))),
+        "friendly" => Some(Box::new(FriendlySpawner(DefaultHeuristic))),
+        _ => None,
+    }
+}
+
+impl Display for SpawnRules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (tile, weight)) in self.weights.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{tile}:{weight}")?;
+        }
+        write!(
+            f,
+            ", {} per move, {} to start",
+            self.count, self.start_tiles
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::{Game, GameRng, Move, Status};
+
+    #[test]
+    fn weights_are_parsed() {
+        assert_eq!(
+            parse_weights("2:0.9,4:0.1"),
+            Ok(vec![(Tile::TWO, 0.9), (Tile::FOUR, 0.1)])
+        );
+        assert_eq!(parse_weights(" 8 : 3 "), Ok(vec![(Tile::EIGHT, 3.0)]));
+        assert!(parse_weights("3:1").is_err());
+        assert!(parse_weights("0:1").is_err());
+        assert!(parse_weights("2").is_err());
+        assert!(parse_weights("2:often").is_err());
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        assert!(SpawnRules::new(vec![], 1, 2).is_err());
+        assert!(SpawnRules::new(vec![(Tile::TWO, 0.0)], 1, 2).is_err());
+        assert!(SpawnRules::new(vec![(Tile::TWO, f64::NAN)], 1, 2).is_err());
+        assert!(SpawnRules::new(vec![(Tile::TWO, 1.0)], 0, 2).is_err());
+        assert!(SpawnRules::new(vec![(Tile::TWO, 1.0)], 1, 0).is_ok());
+    }
+
+    #[test]
+    fn tiles_spawn_by_weight() {
+        let rules = SpawnRules::new(vec![(Tile::TWO, 3.0), (Tile::EIGHT, 1.0)], 1, 2).unwrap();
+        assert_eq!(rules.probability(Tile::EIGHT), 0.25);
+        assert_eq!(rules.probability(Tile::FOUR), 0.0);
+
+        let mut rng = GameRng::seed_from_u64(1);
+        let eights = (0..4000)
+            .filter(|_| rules.pick(&mut rng) == Tile::EIGHT)
+            .count();
+        assert!((900..1100).contains(&eights), "{eights} eights");
+    }
+
+    #[test]
+    fn builtin_names() {
+        for name in BUILTIN {
+            assert_eq!(builtin(name).unwrap().name(), name);
+        }
+        assert!(builtin("kind").is_none());
+    }
+
+    #[test]
+    fn evil_spawner_takes_the_last_move_away() {
+        // A 2 in the only empty cell can still merge, a 4 leaves no move
+        let t = |value| Tile::from_score(value).unwrap();
+        let board = vec![
+            vec![t(4), t(8), t(16), t(0)],
+            vec![t(8), t(16), t(32), t(2)],
+            vec![t(4), t(8), t(16), t(32)],
+            vec![t(8), t(16), t(32), t(64)],
+        ];
+        let mut rng = GameRng::seed_from_u64(0);
+        let spawn = EvilSpawner(DefaultHeuristic)
+            .spawn(&board, &SpawnRules::default(), &mut rng)
+            .unwrap();
+        assert_eq!(
+            spawn,
+            Spawn {
+                at: (0, 3),
+                tile: Tile::FOUR
+            }
+        );
+    }
+
+    #[test]
+    fn evil_games_end_sooner() {
+        let play = |spawner: &str| {
+            let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 3);
+            game.set_spawner(builtin(spawner).unwrap());
+            let mut turns = 0;
+            while let Status::On = game.status() {
+                assert!(
+                    [Move::Down, Move::Left, Move::Right, Move::Up]
+                        .into_iter()
+                        .any(|mov| game.mover(mov))
+                );
+                game.refresh();
+                turns += 1;
+            }
+            turns
+        };
+        assert!(play("evil") < play("random"));
+    }
+
+    #[test]
+    fn sequence_replays_after_undo() {
+        let board = vec![vec![Tile::TWO, Tile::EMPTY], vec![Tile::EMPTY, Tile::EMPTY]];
+        let mut game = Game::from_board(board, Tile::SIXTEEN, 0).unwrap();
+        game.set_spawner(Box::new(SequenceSpawner::new(vec![
+            (Tile::FOUR, Some((0, 1))),
+            (Tile::EIGHT, None),
+        ])));
+
+        // The 2 slides over the cell the 4 was meant for, it takes the first empty one
+        let first = game.play(Move::Right).unwrap();
+        assert_eq!(first.spawns[0].at, (0, 0));
+        let second = game.play(Move::Down).unwrap();
+        assert_eq!(
+            second.spawns,
+            vec![Spawn {
+                at: (0, 0),
+                tile: Tile::EIGHT
+            }]
+        );
+        assert!(game.play(Move::Up).unwrap().spawns.is_empty());
+
+        assert!(game.undo());
+        assert!(game.undo());
+        assert_eq!(game.play(Move::Down), Some(second));
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+use twozero48::{Game, record::Recording, save::LoadError};
+
+const AUTOSAVE_FILE: &str = "autosave.json";
+
+/// Directory to keep files between runs in, e.g. `~/.local/share/twozero48` on Linux.
+/// It is created if it doesn't exist yet.
+pub fn data_dir() -> io::Result<PathBuf> {
+    let dir = dirs::data_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory found"))?
+        .join("twozero48");
+    fs::create_dir_all(&dir)?;
+
+    Ok(dir)
+}
+
+/// Location of the game saved automatically on quit
+pub fn autosave_path() -> io::Result<PathBuf> {
+    Ok(data_dir()?.join(AUTOSAVE_FILE))
+}
+
+/// Saves the game so an existing save is never left half written, see [`write_atomically`]
+pub fn save_game(game: &Game, path: &Path) -> io::Result<()> {
+    let mut contents = vec![];
+    game.save(&mut contents)?;
+    write_atomically(path, &contents)
+}
+
+pub fn load_game(path: &Path) -> Result<Game, LoadError> {
+    let file = File::open(path).map_err(LoadError::Io)?;
+    Game::load(BufReader::new(file))
+}
+
+/// Writes the recording the same way as [`save_game`]
+pub fn save_recording(recording: &Recording, path: &Path) -> io::Result<()> {
+    let mut contents = vec![];
+    recording.save(&mut contents)?;
+    write_atomically(path, &contents)
+}
+
+pub fn load_recording(path: &Path) -> Result<Recording, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    Recording::load(BufReader::new(file))
+}
+
+/// Reads a JSON file, a missing file is the default value, e.g. an empty table
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> io::Result<T> {
+    match File::open(path) {
+        Ok(file) => serde_json::from_reader(BufReader::new(file)).map_err(io::Error::other),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes the value as pretty JSON, see [`write_atomically`]
+pub fn store_json<T: Serialize>(value: &T, path: &Path) -> io::Result<()> {
+    let contents = serde_json::to_vec_pretty(value).map_err(io::Error::other)?;
+    write_atomically(path, &contents)
+}
+
+/// Writes the contents to a temporary file first and moves it over the path once complete,
+/// the temporary file is removed if either step fails
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    let written = fs::write(&tmp, contents).and_then(|()| fs::rename(&tmp, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+
+    written
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refused_saves_leave_no_files_behind() {
+        let dir = std::env::temp_dir().join(format!("twozero48-storage-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("puzzle.json");
+
+        let puzzle = twozero48::puzzle::builtin().remove(0).game();
+        assert!(save_game(&puzzle, &path).is_err());
+        assert!(!path.exists());
+        assert!(!path.with_extension("tmp").exists());
+
+        let game = Game::with_seed(4, twozero48::Tile::TWO_THOUSAND_FORTY_EIGHT, 1);
+        save_game(&game, &path).unwrap();
+        assert_eq!(load_game(&path).unwrap().board(), game.board());
+        assert!(!path.with_extension("tmp").exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,208 @@
+//! Strategies pick the next [`Move`] for a board, they play games without a human.
+
+use rand::{Rng, RngExt, SeedableRng};
+
+use crate::{
+    Board, Game, GameRng, Move, Status,
+    bitboard::{BitGame, Bitboard},
+    legal_moves, slide,
+    solver::{Expectimax, Heuristic},
+    spawn::SpawnRules,
+};
+
+/// Names of the strategies that can be built with [`builtin`],
+/// `expectimax` also takes a search depth, e.g. `expectimax:2`
+pub const BUILTIN: [&str; 4] = ["expectimax", "greedy", "corner", "random"];
+
+pub trait Strategy {
+    /// Picks a move that changes the board, `None` if there is none
+    fn choose(&mut self, board: &Board) -> Option<Move>;
+
+    /// Same as [`choose`](Self::choose) for a packed 4x4 board, strategies that can play on
+    /// it without unpacking it pick the same move faster
+    fn choose_bits(&mut self, board: Bitboard) -> Option<Move> {
+        self.choose(&board.to_board())
+    }
+}
+
+/// Whether a move on the board would merge two 32768s, so its score can't be told
+fn overflows(board: Bitboard) -> bool {
+    Move::ALL.into_iter().any(|mov| board.overflows(mov))
+}
+
+impl<H: Heuristic> Strategy for Expectimax<H> {
+    fn choose(&mut self, board: &Board) -> Option<Move> {
+        self.decide(board).map(|decision| decision.mov)
+    }
+
+    fn choose_bits(&mut self, board: Bitboard) -> Option<Move> {
+        self.decide_bits(board).map(|decision| decision.mov)
+    }
+}
+
+/// Plays a move picked uniformly at random
+pub struct RandomMoves(GameRng);
+
+impl RandomMoves {
+    pub fn new(seed: u64) -> Self {
+        Self(GameRng::seed_from_u64(seed))
+    }
+}
+
+impl Strategy for RandomMoves {
+    fn choose(&mut self, board: &Board) -> Option<Move> {
+        let moves: Vec<Move> = legal_moves(board).collect();
+        (!moves.is_empty()).then(|| moves[self.0.random_range(0..moves.len())])
+    }
+
+    fn choose_bits(&mut self, board: Bitboard) -> Option<Move> {
+        let moves: Vec<Move> = board.legal_moves().collect();
+        (!moves.is_empty()).then(|| moves[self.0.random_range(0..moves.len())])
+    }
+}
+
+/// Plays the move that earns the most score right away
+pub struct Greedy;
+
+impl Strategy for Greedy {
+    fn choose(&mut self, board: &Board) -> Option<Move> {
+        legal_moves(board).max_by_key(|&mov| slide(&mut board.clone(), mov))
+    }
+
+    fn choose_bits(&mut self, board: Bitboard) -> Option<Move> {
+        if overflows(board) {
+            return self.choose(&board.to_board());
+        }
+        board.legal_moves().max_by_key(|&mov| board.slide(mov).1)
+    }
+}
+
+/// Keeps tiles in the bottom left corner, moving up only when nothing else is possible
+pub struct Corner;
+
+impl Strategy for Corner {
+    fn choose(&mut self, board: &Board) -> Option<Move> {
+        [Move::Down, Move::Left, Move::Right, Move::Up]
+            .into_iter()
+            .find(|&mov| legal_moves(board).any(|legal| legal == mov))
+    }
+
+    fn choose_bits(&mut self, board: Bitboard) -> Option<Move> {
+        [Move::Down, Move::Left, Move::Right, Move::Up]
+            .into_iter()
+            .find(|&mov| board.legal_moves().any(|legal| legal == mov))
+    }
+}
+
+/// Builds one of the [`BUILTIN`] strategies by name, randomness is drawn from the seed.
+/// Searches expect tiles to spawn by the rules
+pub fn builtin(name: &str, seed: u64, rules: &SpawnRules) -> Option<Box<dyn Strategy + Send>> {
+    let (name, depth) = match name.split_once(':') {
+        Some((name, depth)) => (name, Some(depth.parse().ok()?)),
+        None => (name, None),
+    };
+
+    match (name, depth) {
+        ("expectimax", depth) => {
+            let mut search = depth.map_or_else(Expectimax::default, Expectimax::new);
+            search.set_spawn_rules(rules.clone());
+            Some(Box::new(search))
+        }
+        ("greedy", None) => Some(Box::new(Greedy)),
+        ("corner", None) => Some(Box::new(Corner)),
+        ("random", None) => Some(Box::new(RandomMoves::new(seed))),
+        _ => None,
+    }
+}
+
+/// Lets the strategy play the game until it is won, lost or the strategy gives up
+pub fn play_out<R: Rng + Clone, S: Strategy + ?Sized>(game: &mut Game<R>, strategy: &mut S) {
+    while let Status::On = game.status() {
+        let Some(mov) = strategy.choose(game.board()) else {
+            break;
+        };
+        if !game.mover(mov) {
+            break;
+        }
+        game.refresh();
+    }
+}
+
+/// Same as [`play_out`] on a [`BitGame`]. Stops early with the move the strategy picked if it
+/// merges two 32768s, to be made on the game [`BitGame::to_game`] hands back
+pub fn play_out_bits<R: Rng + Clone, S: Strategy + ?Sized>(
+    game: &mut BitGame<R>,
+    strategy: &mut S,
+) -> Option<Move> {
+    while let Status::On = game.status() {
+        let mov = strategy.choose_bits(game.board())?;
+        match game.mover(mov) {
+            Ok(true) => game.refresh(),
+            Ok(false) => break,
+            Err(_) => return Some(mov),
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Tile;
+
+    #[test]
+    fn builtin_names() {
+        for name in BUILTIN {
+            assert!(builtin(name, 0, &SpawnRules::default()).is_some());
+        }
+        assert!(builtin("expectimax:2", 0, &SpawnRules::default()).is_some());
+        assert!(builtin("expectimax:deep", 0, &SpawnRules::default()).is_none());
+        assert!(builtin("greedy:2", 0, &SpawnRules::default()).is_none());
+        assert!(builtin("unknown", 0, &SpawnRules::default()).is_none());
+    }
+
+    #[test]
+    fn greedy_takes_the_biggest_merge() {
+        let board = vec![vec![Tile::TWO, Tile::FOUR], vec![Tile::EIGHT, Tile::FOUR]];
+        assert!(matches!(Greedy.choose(&board), Some(Move::Up | Move::Down)));
+    }
+
+    #[test]
+    fn strategies_play_until_the_end() {
+        for name in ["greedy", "corner", "random"] {
+            let mut strategy = builtin(name, 5, &SpawnRules::default()).unwrap();
+            let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 5);
+            play_out(&mut game, strategy.as_mut());
+            assert!(!matches!(game.status(), Status::On));
+        }
+    }
+
+    #[test]
+    fn bitboards_get_the_same_moves() {
+        for name in ["expectimax:1", "greedy", "corner", "random"] {
+            let rules = SpawnRules::default();
+            let mut strategy = builtin(name, 8, &rules).unwrap();
+            let mut packed = builtin(name, 8, &rules).unwrap();
+            let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 8);
+            let mut fast = BitGame::from_game(&game).unwrap();
+            play_out(&mut game, strategy.as_mut());
+            assert_eq!(play_out_bits(&mut fast, packed.as_mut()), None);
+            assert_eq!(fast.board().to_board(), *game.board());
+            assert_eq!(fast.moves(), game.moves());
+        }
+
+        // Greedy can't score a merge of two 32768s on a bitboard, it asks the board instead
+        let board: Board = [
+            [32768, 32768, 2, 4],
+            [2, 4, 8, 16],
+            [4, 8, 16, 32],
+            [8, 16, 32, 64],
+        ]
+        .iter()
+        .map(|row| row.iter().map(|&v| Tile::from_score(v).unwrap()).collect())
+        .collect();
+        let packed = Bitboard::from_board(&board).unwrap();
+        assert_eq!(Greedy.choose_bits(packed), Greedy.choose(&board));
+    }
+}
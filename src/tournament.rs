@@ -0,0 +1,729 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use clap::{Args, value_parser};
+use serde::Serialize;
+use twozero48::{
+    Game, Status, Tile,
+    engine::{self, GameOptions, Response, Session},
+    spawn::SpawnRules,
+    strategy::{self, BUILTIN},
+};
+
+use crate::simulate::Format;
+
+/// Number of tile thresholds the leaderboard reports win rates for when they are picked
+/// from the tiles reached
+const AUTO_THRESHOLDS: usize = 5;
+
+/// Options of the `tournament` subcommand
+#[derive(Args)]
+pub struct TournamentOpts {
+    /// Bots to compare, built-in strategies like expectimax:2 or greedy, or commands
+    /// starting programs that speak the engine protocol, e.g. "python3 bot.py"
+    #[clap(long, value_delimiter = ',', required = true)]
+    pub bots: Vec<String>,
+    /// Seeds every bot plays a game of, both ends included, e.g. 1..500
+    #[clap(long, default_value = "1..100", value_parser = parse_seeds)]
+    pub seeds: RangeInclusive<u64>,
+    /// Milliseconds a program has to send each request before its game is ended
+    #[clap(long, default_value = "5000", value_parser = value_parser!(u64).range(1..))]
+    pub move_time: u64,
+    /// Number of games played at once, defaults to the number of CPUs
+    #[clap(long)]
+    pub threads: Option<usize>,
+    /// Format the leaderboard is printed in
+    #[clap(long, value_enum, default_value = "table")]
+    pub format: Format,
+    /// File to write the result of every game to, as CSV
+    #[clap(long)]
+    pub results: Option<PathBuf>,
+    /// Tiles the leaderboard reports the share of games reaching, the same for every
+    /// tournament so they can be compared. `auto` picks the largest tiles this one reached
+    #[clap(long, default_value = "512,1024,2048,4096,8192", value_parser = parse_thresholds)]
+    pub thresholds: Thresholds,
+}
+
+/// Tile values the leaderboard reports on in increasing order, none to pick them from the
+/// tiles reached
+#[derive(Clone, Debug, PartialEq)]
+pub struct Thresholds(Vec<usize>);
+
+/// Parses thresholds written as tile values, e.g. `1024,2048`, or `auto`
+fn parse_thresholds(thresholds: &str) -> Result<Thresholds, String> {
+    if thresholds.trim() == "auto" {
+        return Ok(Thresholds(vec![]));
+    }
+    let mut tiles = thresholds
+        .split(',')
+        .map(|tile| {
+            tile.trim()
+                .parse()
+                .ok()
+                .and_then(Tile::from_score)
+                .filter(|&tile| tile > Tile::TWO)
+                .map(|tile| tile.score())
+                .ok_or_else(|| format!("{tile} is not a tile value, e.g. 2048"))
+        })
+        .collect::<Result<Vec<usize>, _>>()?;
+    tiles.sort_unstable();
+    tiles.dedup();
+
+    Ok(Thresholds(tiles))
+}
+
+/// Parses seeds written as `first..last` or a single seed
+fn parse_seeds(seeds: &str) -> Result<RangeInclusive<u64>, String> {
+    let parse = |seed: &str| {
+        seed.trim()
+            .parse::<u64>()
+            .map_err(|_| format!("{seed} is not a seed, e.g. 1..500"))
+    };
+    let (first, last) = match seeds.split_once("..") {
+        Some((first, last)) => (parse(first)?, parse(last.trim_start_matches('='))?),
+        None => (parse(seeds)?, parse(seeds)?),
+    };
+    if first > last {
+        return Err(format!(
+            "{seeds} has no seeds, the first one comes after the last"
+        ));
+    }
+
+    Ok(first..=last)
+}
+
+/// Something that plays games
+#[derive(Clone, Debug, PartialEq)]
+enum Bot {
+    /// One of the strategies of [`strategy::builtin`]
+    Builtin(String),
+    /// A program and its arguments, started for every game and played with over its
+    /// stdin and stdout
+    Program(Vec<String>),
+}
+
+impl Bot {
+    fn parse(bot: &str) -> Result<Self, String> {
+        if strategy::builtin(bot, 0, &SpawnRules::default()).is_some() {
+            return Ok(Bot::Builtin(bot.to_string()));
+        }
+        let command: Vec<String> = bot.split_whitespace().map(str::to_string).collect();
+        if command.is_empty() {
+            return Err(format!(
+                "bots are built-in strategies, one of: {}, or commands",
+                BUILTIN.join(", ")
+            ));
+        }
+
+        Ok(Bot::Program(command))
+    }
+
+    /// Plays the game to the end, errors are reserved for programs that can't be started.
+    /// Games continue past the winning tile, so bots are compared on how far they get.
+    fn play(&self, mut game: Game, move_time: Duration) -> Result<GameResult, String> {
+        let seed = game.seed().expect("tournament games are seeded");
+        let fault = match self {
+            Bot::Builtin(name) => {
+                let mut strategy =
+                    strategy::builtin(name, seed, game.spawn_rules()).expect("bots were validated");
+                loop {
+                    match game.status() {
+                        Status::Lost(_) => break,
+                        Status::Won => game.keep_playing(),
+                        Status::On | Status::WonContinuing => {}
+                    }
+                    let Some(mov) = strategy.choose(game.board()) else {
+                        break;
+                    };
+                    if !game.mover(mov) {
+                        break;
+                    }
+                    game.refresh();
+                }
+                None
+            }
+            Bot::Program(command) => {
+                let mut session = Session::restricted(game);
+                let fault = converse(command, &mut session, move_time)?;
+                game = session.into_game();
+                fault
+            }
+        };
+
+        Ok(GameResult {
+            seed,
+            score: game.score(),
+            moves: game.moves(),
+            largest_tile: game.largest_tile().score(),
+            fault,
+        })
+    }
+}
+
+/// Plays the session with a program until the game is lost or the program quits, returns
+/// what the program did wrong if it stopped early
+fn converse(
+    command: &[String],
+    session: &mut Session,
+    move_time: Duration,
+) -> Result<Option<String>, String> {
+    let mut child = process::Command::new(&command[0])
+        .args(&command[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("couldn't start {}: {e}", command.join(" ")))?;
+    let mut stdin = BufWriter::new(child.stdin.take().expect("stdin is piped"));
+    let stdout = child.stdout.take().expect("stdout is piped");
+
+    // Lines are read on a thread of their own, so a program that hangs can be timed out
+    let (lines, requests) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            if lines.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut response = session.state();
+    let fault = loop {
+        let sent = engine::send(&mut stdin, &response);
+        match response {
+            // The program may exit as soon as it quits, it doesn't have to read the goodbye
+            Response::Bye => break None,
+            _ if sent.is_err() => break Some("stopped reading its input".to_string()),
+            Response::Error { message, .. } => break Some(message),
+            Response::State(_) if matches!(session.game().status(), Status::Lost(_)) => {
+                break None;
+            }
+            Response::State(_) => {}
+        }
+
+        let deadline = Instant::now() + move_time;
+        let line = loop {
+            match requests.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(Ok(line)) if line.trim().is_empty() => continue,
+                received => break received,
+            }
+        };
+        let line = match line {
+            Ok(Ok(line)) => line,
+            Ok(Err(e)) => break Some(e.to_string()),
+            Err(RecvTimeoutError::Timeout) => {
+                break Some(format!(
+                    "no request came within {}ms",
+                    move_time.as_millis()
+                ));
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                break Some("exited before the game was over".to_string());
+            }
+        };
+        response = session.handle(&line);
+    };
+    drop(stdin);
+    let _ = child.kill();
+    let _ = child.wait();
+
+    Ok(fault)
+}
+
+/// Outcome of a bot's game on one seed
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameResult {
+    seed: u64,
+    score: usize,
+    moves: usize,
+    largest_tile: usize,
+    /// What a program did wrong, its game ended where it was
+    fault: Option<String>,
+}
+
+/// Results of every bot, seed by seed
+pub type GamesByBot = Vec<(String, Vec<GameResult>)>;
+
+/// How a bot did over every seed
+#[derive(Serialize)]
+pub struct Standing {
+    bot: String,
+    games: usize,
+    mean_score: f64,
+    median_score: usize,
+    mean_moves: f64,
+    /// Games a program ended by breaking the protocol or running out of time
+    faults: usize,
+    /// Share of games that reached each tile of [`Leaderboard::thresholds`]
+    reached: Vec<f64>,
+    /// Seeds this bot scored more on than each other bot, in leaderboard order
+    beat: Vec<usize>,
+}
+
+/// Every bot ranked by mean score
+#[derive(Serialize)]
+pub struct Leaderboard {
+    first_seed: u64,
+    last_seed: u64,
+    thresholds: Vec<usize>,
+    standings: Vec<Standing>,
+}
+
+/// Plays a game of every seed with every bot, each seed's game is the same for every bot
+pub fn run(
+    opts: &TournamentOpts,
+    options: &GameOptions,
+) -> Result<(Leaderboard, GamesByBot), String> {
+    let bots: Vec<Bot> = opts
+        .bots
+        .iter()
+        .map(|bot| Bot::parse(bot))
+        .collect::<Result<_, _>>()?;
+    options.game()?;
+    let seeds: Vec<u64> = opts.seeds.clone().collect();
+    let move_time = Duration::from_millis(opts.move_time);
+    let games = bots.len() * seeds.len();
+    let threads = opts
+        .threads
+        .or_else(|| thread::available_parallelism().ok().map(usize::from))
+        .unwrap_or(1)
+        .clamp(1, games.max(1));
+
+    let next = AtomicUsize::new(0);
+    let error = Mutex::new(None);
+    let mut results: Vec<(usize, GameResult)> = thread::scope(|s| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut results = vec![];
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        if i >= games {
+                            return results;
+                        }
+                        let game = GameOptions {
+                            seed: Some(seeds[i % seeds.len()]),
+                            ..options.clone()
+                        }
+                        .game()
+                        .expect("options were validated");
+                        match bots[i / seeds.len()].play(game, move_time) {
+                            Ok(result) => results.push((i, result)),
+                            Err(e) => {
+                                // Nothing is left to play once a bot can't be started
+                                next.store(games, Ordering::Relaxed);
+                                error.lock().unwrap().get_or_insert(e);
+                                return results;
+                            }
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("tournament thread panicked"))
+            .collect()
+    });
+    if let Some(e) = error.into_inner().unwrap() {
+        return Err(e);
+    }
+    results.sort_by_key(|(i, _)| *i);
+
+    let mut results = results.into_iter().map(|(_, result)| result);
+    let by_bot: GamesByBot = opts
+        .bots
+        .iter()
+        .map(|bot| (bot.clone(), results.by_ref().take(seeds.len()).collect()))
+        .collect();
+
+    Ok((leaderboard(&opts.seeds, &opts.thresholds, &by_bot), by_bot))
+}
+
+/// Ranks the bots, without thresholds they are the largest tiles any game reached
+fn leaderboard(
+    seeds: &RangeInclusive<u64>,
+    thresholds: &Thresholds,
+    by_bot: &[(String, Vec<GameResult>)],
+) -> Leaderboard {
+    let thresholds = if thresholds.0.is_empty() {
+        let largest = by_bot
+            .iter()
+            .flat_map(|(_, results)| results)
+            .map(|result| result.largest_tile)
+            .max()
+            .unwrap_or(0);
+        (0..AUTO_THRESHOLDS)
+            .rev()
+            .map(|shift| largest >> shift)
+            .filter(|&tile| tile >= 4)
+            .collect()
+    } else {
+        thresholds.0.clone()
+    };
+
+    let mut order: Vec<usize> = (0..by_bot.len()).collect();
+    let mean = |results: &[GameResult], value: fn(&GameResult) -> usize| {
+        results.iter().map(value).sum::<usize>() as f64 / results.len().max(1) as f64
+    };
+    order.sort_by(|&a, &b| {
+        mean(&by_bot[b].1, |r| r.score).total_cmp(&mean(&by_bot[a].1, |r| r.score))
+    });
+
+    let standings = order
+        .iter()
+        .map(|&i| {
+            let (bot, results) = &by_bot[i];
+            let mut scores: Vec<usize> = results.iter().map(|result| result.score).collect();
+            scores.sort_unstable();
+            Standing {
+                bot: bot.clone(),
+                games: results.len(),
+                mean_score: mean(results, |r| r.score),
+                median_score: scores.get(scores.len() / 2).copied().unwrap_or(0),
+                mean_moves: mean(results, |r| r.moves),
+                faults: results
+                    .iter()
+                    .filter(|result| result.fault.is_some())
+                    .count(),
+                reached: thresholds
+                    .iter()
+                    .map(|&tile| {
+                        results.iter().filter(|r| r.largest_tile >= tile).count() as f64
+                            / results.len().max(1) as f64
+                    })
+                    .collect(),
+                beat: order
+                    .iter()
+                    .map(|&other| {
+                        results
+                            .iter()
+                            .zip(&by_bot[other].1)
+                            .filter(|(ours, theirs)| ours.score > theirs.score)
+                            .count()
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+
+    Leaderboard {
+        first_seed: *seeds.start(),
+        last_seed: *seeds.end(),
+        thresholds,
+        standings,
+    }
+}
+
+pub fn print(leaderboard: &Leaderboard, format: Format) -> Result<(), serde_json::Error> {
+    match format {
+        Format::Table => print_table(leaderboard),
+        Format::Json => println!("{}", serde_json::to_string_pretty(leaderboard)?),
+        Format::Csv => print_csv(leaderboard),
+    }
+
+    Ok(())
+}
+
+fn print_table(leaderboard: &Leaderboard) {
+    let width = leaderboard
+        .standings
+        .iter()
+        .map(|standing| standing.bot.len())
+        .max()
+        .unwrap_or(0)
+        .max(3);
+    println!(
+        "{} bots on seeds {} to {}",
+        leaderboard.standings.len(),
+        leaderboard.first_seed,
+        leaderboard.last_seed
+    );
+    println!();
+
+    print!(
+        "{:>2}  {:<width$}  {:>10}  {:>8}  {:>8}  {:>6}",
+        "#", "Bot", "Mean score", "Median", "Moves", "Faults"
+    );
+    for tile in &leaderboard.thresholds {
+        print!("  {:>6}", format!("≥{tile}"));
+    }
+    println!();
+    for (rank, standing) in leaderboard.standings.iter().enumerate() {
+        print!(
+            "{:>2}  {:<width$}  {:>10.1}  {:>8}  {:>8.1}  {:>6}",
+            rank + 1,
+            standing.bot,
+            standing.mean_score,
+            standing.median_score,
+            standing.mean_moves,
+            standing.faults
+        );
+        for rate in &standing.reached {
+            print!("  {:>5.1}%", rate * 100.0);
+        }
+        println!();
+    }
+    println!();
+
+    println!("Head to head, seeds the row's bot scored more on than the column's");
+    print!("{:<width$}", "");
+    for standing in &leaderboard.standings {
+        print!("  {:>width$}", standing.bot);
+    }
+    println!();
+    for (row, standing) in leaderboard.standings.iter().enumerate() {
+        print!("{:<width$}", standing.bot);
+        for (column, beat) in standing.beat.iter().enumerate() {
+            if row == column {
+                print!("  {:>width$}", "-");
+            } else {
+                print!("  {beat:>width$}");
+            }
+        }
+        println!();
+    }
+}
+
+/// Quotes the field if it would break a CSV row
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', ' ']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn print_csv(leaderboard: &Leaderboard) {
+    print!("{}", leaderboard_csv(leaderboard));
+}
+
+/// The leaderboard as CSV, a row per bot after a header row
+fn leaderboard_csv(leaderboard: &Leaderboard) -> String {
+    let mut header = vec![
+        "rank".to_string(),
+        "bot".to_string(),
+        "games".to_string(),
+        "mean_score".to_string(),
+        "median_score".to_string(),
+        "mean_moves".to_string(),
+        "faults".to_string(),
+    ];
+    header.extend(
+        leaderboard
+            .thresholds
+            .iter()
+            .map(|tile| format!("reached_{tile}")),
+    );
+    header.extend(
+        leaderboard
+            .standings
+            .iter()
+            .map(|standing| csv_field(&format!("beat_{}", standing.bot))),
+    );
+    let mut csv = header.join(",") + "\n";
+
+    for (rank, standing) in leaderboard.standings.iter().enumerate() {
+        let mut row = vec![
+            (rank + 1).to_string(),
+            csv_field(&standing.bot),
+            standing.games.to_string(),
+            standing.mean_score.to_string(),
+            standing.median_score.to_string(),
+            standing.mean_moves.to_string(),
+            standing.faults.to_string(),
+        ];
+        row.extend(standing.reached.iter().map(f64::to_string));
+        row.extend(standing.beat.iter().map(usize::to_string));
+        csv += &(row.join(",") + "\n");
+    }
+
+    csv
+}
+
+/// Writes a row for every game played, bot by bot and seed by seed
+pub fn save_results(by_bot: &[(String, Vec<GameResult>)], path: &Path) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "bot,seed,score,moves,largest_tile,fault")?;
+    for (bot, results) in by_bot {
+        for result in results {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{}",
+                csv_field(bot),
+                result.seed,
+                result.score,
+                result.moves,
+                result.largest_tile,
+                result.fault.as_deref().map(csv_field).unwrap_or_default()
+            )?;
+        }
+    }
+
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opts(bots: &[&str], seeds: RangeInclusive<u64>, threads: usize) -> TournamentOpts {
+        TournamentOpts {
+            bots: bots.iter().map(|bot| bot.to_string()).collect(),
+            seeds,
+            move_time: 2000,
+            threads: Some(threads),
+            format: Format::Table,
+            results: None,
+            thresholds: parse_thresholds("512,1024,2048,4096,8192").unwrap(),
+        }
+    }
+
+    #[test]
+    fn seeds_are_parsed() {
+        assert_eq!(parse_seeds("1..500"), Ok(1..=500));
+        assert_eq!(parse_seeds("3..=4"), Ok(3..=4));
+        assert_eq!(parse_seeds("7"), Ok(7..=7));
+        assert!(parse_seeds("5..1").is_err());
+        assert!(parse_seeds("a..b").is_err());
+    }
+
+    #[test]
+    fn thresholds_are_fixed_unless_auto() {
+        assert_eq!(
+            parse_thresholds("2048, 512,2048"),
+            Ok(Thresholds(vec![512, 2048]))
+        );
+        assert!(parse_thresholds("2").is_err());
+        assert!(parse_thresholds("1000").is_err());
+
+        let result = |largest_tile| GameResult {
+            seed: 1,
+            score: 100,
+            moves: 10,
+            largest_tile,
+            fault: None,
+        };
+        let by_bot = vec![("corner".to_string(), vec![result(256), result(1024)])];
+        let fixed = leaderboard(
+            &(1..=2),
+            &parse_thresholds("512,1024,2048").unwrap(),
+            &by_bot,
+        );
+        assert_eq!(fixed.thresholds, vec![512, 1024, 2048]);
+        assert_eq!(fixed.standings[0].reached, vec![0.5, 0.5, 0.0]);
+
+        let auto = leaderboard(&(1..=2), &parse_thresholds("auto").unwrap(), &by_bot);
+        assert_eq!(auto.thresholds, vec![64, 128, 256, 512, 1024]);
+    }
+
+    #[test]
+    fn bots_play_the_same_games() {
+        let options = GameOptions::default();
+        let (one, by_bot) = run(&opts(&["corner", "greedy"], 1..=10, 1), &options).unwrap();
+        let (four, _) = run(&opts(&["corner", "greedy"], 1..=10, 4), &options).unwrap();
+        assert_eq!(
+            serde_json::to_string(&one).unwrap(),
+            serde_json::to_string(&four).unwrap()
+        );
+
+        // Head to head counts can't add up to more than the shared seeds
+        let [a, b] = &one.standings[..] else {
+            panic!("two bots make two standings");
+        };
+        assert_eq!(a.beat[0] + b.beat[1], 0);
+        assert!(a.beat[1] + b.beat[0] <= 10);
+        assert!(a.mean_score >= b.mean_score);
+        assert!(a.reached.windows(2).all(|pair| pair[0] >= pair[1]));
+
+        // Each game is the one a bot gets when it plays the seed on its own
+        let game = GameOptions {
+            seed: Some(1),
+            ..options
+        }
+        .game()
+        .unwrap();
+        let alone = Bot::parse("corner").unwrap().play(game, Duration::ZERO);
+        assert_eq!(alone, Ok(by_bot[0].1[0].clone()));
+    }
+
+    #[test]
+    fn csv_fields_are_quoted_whole() {
+        let result = |score| GameResult {
+            seed: 1,
+            score,
+            moves: 10,
+            largest_tile: 64,
+            fault: None,
+        };
+        let by_bot = vec![
+            ("python3 bot.py, --fast".to_string(), vec![result(500)]),
+            ("greedy".to_string(), vec![result(300)]),
+        ];
+        let csv = leaderboard_csv(&leaderboard(&(1..=1), &Thresholds(vec![]), &by_bot));
+        let mut lines = csv.lines();
+        assert!(
+            lines
+                .next()
+                .unwrap()
+                .ends_with(r#","beat_python3 bot.py, --fast",beat_greedy"#)
+        );
+        assert!(
+            lines
+                .next()
+                .unwrap()
+                .starts_with(r#"1,"python3 bot.py, --fast",1,500,"#)
+        );
+    }
+
+    #[test]
+    fn programs_play_over_the_engine_protocol() {
+        let game = || {
+            GameOptions {
+                seed: Some(3),
+                ..Default::default()
+            }
+            .game()
+            .unwrap()
+        };
+        let program = |script: &str| Bot::Program(vec!["sh".into(), "-c".into(), script.into()]);
+
+        // Always moves left, so its game ends on the first move that doesn't change the board
+        let stubborn =
+            program(r#"while read line; do echo '{"cmd": "move", "dir": "left"}'; done"#)
+                .play(game(), Duration::from_secs(2))
+                .unwrap();
+        assert!(stubborn.fault.unwrap().contains("left"));
+
+        let quitter = program(r#"read line; echo '{"cmd": "quit"}'"#)
+            .play(game(), Duration::from_secs(2))
+            .unwrap();
+        assert_eq!(quitter.fault, None);
+        assert_eq!(quitter.moves, 0);
+
+        let sleeper = program("sleep 5")
+            .play(game(), Duration::from_millis(100))
+            .unwrap();
+        assert!(sleeper.fault.unwrap().contains("100ms"));
+
+        assert!(
+            Bot::parse("/nonexistent/bot")
+                .unwrap()
+                .play(game(), Duration::from_secs(1))
+                .is_err()
+        );
+    }
+}
@@ -1,5 +1,4 @@
 use std::{
-    collections::HashSet,
     io,
     time::{Duration, Instant},
 };
@@ -9,21 +8,25 @@ use crossterm::{
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use ratatui::{
-    Terminal,
+    Frame, Terminal,
     backend::CrosstermBackend,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Paragraph},
 };
-use twozero48::{Game, Move, Tile};
+use twozero48::{Game, Move, MoveOutcome, Spawn, Tile};
+
+use crate::{autoplay::Autoplay, countdown};
 
 const CELL_W: u16 = 10;
 const CELL_H: u16 = 5;
 const HEADER_H: u16 = 2;
 const FOOTER_H: u16 = 1;
 const EMPTY_BG: Color = Color::Rgb(40, 40, 40);
-const FLASH_DURATION: Duration = Duration::from_millis(120);
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+const SLIDE_DURATION: Duration = Duration::from_millis(100);
+const POP_DURATION: Duration = Duration::from_millis(120);
 
 fn brighten(color: Color, amt: u8) -> Color {
     match color {
@@ -36,127 +39,206 @@ fn brighten(color: Color, amt: u8) -> Color {
     }
 }
 
-struct AnimState {
-    dx: i16,
-    dy: i16,
-    started: Instant,
+/// Stage a move's animation is in, along with its progress from 0 to 1
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Phase {
+    /// Tiles travel from where they were to where they end up
+    Slide(f32),
+    /// Merged tiles pop and the spawned tile grows in
+    Pop(f32),
 }
 
-impl AnimState {
-    fn new(mov: Move) -> Option<Self> {
-        let (dx, dy) = match mov {
-            Move::Left => (-2, 0),
-            Move::Right => (2, 0),
-            Move::Up => (0, -2),
-            Move::Down => (0, 2),
-            Move::Dont => return None,
-        };
-
-        Some(Self {
-            dx,
-            dy,
-            started: Instant::now(),
-        })
-    }
-
-    fn shift(&self) -> (i16, i16) {
-        let elapsed = self.started.elapsed().as_millis().min(i16::MAX as u128) as i16;
-        let step = (elapsed / 65).min(3);
-        let remaining = (3 - step).max(0);
-        (self.dx * remaining * 2 / 3, self.dy * remaining * 2 / 3)
+impl Phase {
+    /// Phase of an animation that started `elapsed` ago, `None` once it is over
+    fn at(elapsed: Duration) -> Option<Self> {
+        if elapsed < SLIDE_DURATION {
+            Some(Phase::Slide(ease_out(
+                elapsed.as_secs_f32() / SLIDE_DURATION.as_secs_f32(),
+            )))
+        } else if elapsed < SLIDE_DURATION + POP_DURATION {
+            Some(Phase::Pop(
+                (elapsed - SLIDE_DURATION).as_secs_f32() / POP_DURATION.as_secs_f32(),
+            ))
+        } else {
+            None
+        }
     }
+}
 
-    fn expired(&self) -> bool {
-        self.started.elapsed() >= Duration::from_millis(200)
-    }
+/// Starts fast and slows down towards the end, like a tile sliding to a stop
+fn ease_out(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
 }
 
+/// Animates the last move on the board. Progress follows the time since the move
+/// was played, so it doesn't depend on how often the board is drawn.
 pub struct MoveEffects {
-    anim: Option<AnimState>,
-    flash: HashSet<(usize, usize)>,
-    flash_until: Option<Instant>,
+    enabled: bool,
+    outcome: Option<MoveOutcome>,
+    started: Instant,
 }
 
 impl MoveEffects {
-    pub fn new() -> Self {
+    pub fn new(enabled: bool) -> Self {
         Self {
-            anim: None,
-            flash: HashSet::new(),
-            flash_until: None,
+            enabled,
+            outcome: None,
+            started: Instant::now(),
         }
     }
 
-    pub fn record_move(&mut self, mov: Move, old_board: &[Vec<Tile>], new_board: &[Vec<Tile>]) {
-        self.anim = AnimState::new(mov);
-        self.flash = changed_cells(old_board, new_board);
-        self.flash_until = Some(Instant::now() + FLASH_DURATION);
+    pub fn record_move(&mut self, outcome: MoveOutcome) {
+        if self.enabled {
+            self.outcome = Some(outcome);
+            self.started = Instant::now();
+        }
     }
 
+    /// Drops the animation once it is over
     pub fn tick(&mut self) {
-        if self
-            .flash_until
-            .map(|t| Instant::now() >= t)
-            .unwrap_or(false)
-        {
-            self.flash.clear();
-            self.flash_until = None;
-        }
-
-        if self.anim.as_ref().map(|a| a.expired()).unwrap_or(false) {
-            self.anim = None;
+        if self.phase().is_none() {
+            self.outcome = None;
         }
     }
 
-    pub fn shift(&self) -> (i16, i16) {
-        self.anim.as_ref().map(|a| a.shift()).unwrap_or((0, 0))
+    fn phase(&self) -> Option<Phase> {
+        self.outcome.as_ref()?;
+        Phase::at(self.started.elapsed())
     }
 
-    pub fn flash(&self) -> &HashSet<(usize, usize)> {
-        &self.flash
-    }
-
-    pub fn is_active(&self) -> bool {
-        self.anim.is_some() || self.flash_until.is_some()
+    /// Time until the next frame should be drawn, `None` if nothing is animating
+    pub fn next_frame(&self) -> Option<Duration> {
+        self.outcome.as_ref()?;
+        let elapsed = self.started.elapsed().as_nanos();
+        let interval = FRAME_INTERVAL.as_nanos();
+        Some(Duration::from_nanos((interval - elapsed % interval) as u64))
     }
 
     pub fn clear(&mut self) {
-        self.anim = None;
-        self.flash.clear();
-        self.flash_until = None;
+        self.outcome = None;
     }
 }
 
-fn changed_cells(old: &[Vec<Tile>], new: &[Vec<Tile>]) -> HashSet<(usize, usize)> {
-    let mut set = HashSet::new();
-    for (r, (old_row, new_row)) in old.iter().zip(new.iter()).enumerate() {
-        for (c, (&ov, &nv)) in old_row.iter().zip(new_row.iter()).enumerate() {
-            if nv != ov && nv != Tile::Empty {
-                set.insert((r, c));
-            }
+/// Mixes two colors, `t` of 0 is `from` and 1 is `to`
+fn blend(from: Color, to: Color, t: f32) -> Color {
+    match (from, to) {
+        (Color::Rgb(r1, g1, b1), Color::Rgb(r2, g2, b2)) => {
+            let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+            Color::Rgb(mix(r1, r2), mix(g1, g2), mix(b1, b2))
         }
+        _ => to,
+    }
+}
+
+/// Draws a tile with its top left corner at `(x, y)`, skipped if it doesn't fit on screen
+fn draw_tile(f: &mut Frame, (x, y): (i16, i16), (w, h): (u16, u16), tile: Tile, bg: Color) {
+    let area = f.area();
+    if x < 0 || y < 0 || x as u16 + w > area.width || y as u16 + h > area.height {
+        return;
+    }
+    let cell_area = Rect::new(x as u16, y as u16, w, h);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .style(Style::default().bg(bg));
+    let inner = block.inner(cell_area);
+    f.render_widget(block, cell_area);
+
+    if tile != Tile::EMPTY && !tile.is_blocked() && inner.width > 0 && inner.height > 0 {
+        let text = Paragraph::new(Span::styled(
+            tile_label(tile, inner.width as usize),
+            Style::default()
+                .fg(Color::White)
+                .bg(bg)
+                .add_modifier(Modifier::BOLD),
+        ))
+        .alignment(Alignment::Center);
+        let mid_y = inner.y + inner.height / 2;
+        f.render_widget(text, Rect::new(inner.x, mid_y, inner.width, 1));
     }
-    set
 }
 
+/// Colors of the tiles past 4096, repeated for even larger tiles
+const LARGE_TILE_COLORS: [Color; 5] = [
+    Color::Rgb(255, 60, 140),
+    Color::Rgb(190, 40, 40),
+    Color::Rgb(120, 60, 200),
+    Color::Rgb(40, 60, 160),
+    Color::Rgb(0, 130, 130),
+];
+
 /// Returns the [`Color`] of the tile(used for rendering)
 fn tile_color(tile: Tile) -> Color {
-    match tile {
-        Tile::Empty => Color::Rgb(180, 180, 180),
-        Tile::Two => Color::Rgb(255, 220, 80),
-        Tile::Four => Color::Rgb(255, 165, 30),
-        Tile::Eight => Color::Rgb(255, 100, 20),
-        Tile::Sixteen => Color::Rgb(240, 50, 50),
-        Tile::ThirtyTwo => Color::Rgb(200, 20, 120),
-        Tile::SixtyFour => Color::Rgb(150, 0, 200),
-        Tile::OneHundredTwentyEight => Color::Rgb(80, 20, 220),
-        Tile::TwoHundredFiftySix => Color::Rgb(20, 100, 255),
-        Tile::FiveHundredTwelve => Color::Rgb(0, 200, 220),
-        Tile::OneThousandTwentyFour => Color::Rgb(20, 220, 120),
-        Tile::TwoThousandFourtyEight => Color::Rgb(255, 215, 0),
-        Tile::FourHundredNinetySix => Color::Rgb(255, 255, 255),
+    match tile.exponent() {
+        0 => Color::Rgb(180, 180, 180),
+        1 => Color::Rgb(255, 220, 80),
+        2 => Color::Rgb(255, 165, 30),
+        3 => Color::Rgb(255, 100, 20),
+        4 => Color::Rgb(240, 50, 50),
+        5 => Color::Rgb(200, 20, 120),
+        6 => Color::Rgb(150, 0, 200),
+        7 => Color::Rgb(80, 20, 220),
+        8 => Color::Rgb(20, 100, 255),
+        9 => Color::Rgb(0, 200, 220),
+        10 => Color::Rgb(20, 220, 120),
+        11 => Color::Rgb(255, 215, 0),
+        12 => Color::Rgb(255, 255, 255),
+        u8::MAX => Color::Rgb(60, 60, 60),
+        exponent => LARGE_TILE_COLORS[(exponent as usize - 13) % LARGE_TILE_COLORS.len()],
+    }
+}
+
+/// Returns the tile's value as text of at most `width` characters,
+/// values that are too long are shortened with a metric suffix, e.g. 131072 as "131k"
+fn tile_label(tile: Tile, width: usize) -> String {
+    let label = tile.to_string();
+    if label.len() <= width {
+        return label;
+    }
+
+    let mut value = tile.score();
+    for suffix in ["k", "M", "G", "T", "P", "E"] {
+        value /= 1000;
+        let label = format!("{value}{suffix}");
+        if label.len() <= width {
+            return label;
+        }
+    }
+
+    label
+}
+
+/// Returns the arrow pointing in the direction of the [`Move`]
+fn arrow(mov: Move) -> &'static str {
+    match mov {
+        Move::Left => "◀",
+        Move::Right => "▶",
+        Move::Up => "▲",
+        Move::Down => "▼",
+        Move::Dont => "",
     }
 }
 
+/// Information shown around the board that isn't part of the [`Game`] itself
+#[derive(Default)]
+pub struct Hud<'a> {
+    /// Best score recorded under the game's rules
+    pub best: Option<usize>,
+    /// Replaces the controls in the footer
+    pub message: Option<&'a str>,
+    /// Move suggested by the solver, pointed at from the side of the board
+    pub hint: Option<Move>,
+    /// Set while the solver is playing the game
+    pub autoplay: Option<&'a Autoplay>,
+    /// Shown next to the score, e.g. whose turn it is or the name of the puzzle
+    pub banner: Option<&'a str>,
+    /// Tile player two is about to place, drawn under a cursor
+    pub placement: Option<Spawn>,
+    /// Time left on the clock, shown next to the score
+    pub clock: Option<Duration>,
+}
+
 pub struct TermGuard(Terminal<CrosstermBackend<io::Stdout>>);
 
 impl TermGuard {
@@ -180,18 +262,16 @@ impl TermGuard {
     pub fn render_board(
         &mut self,
         game: &Game,
-        message: Option<&str>,
-        x_shift: i16,
-        y_shift: i16,
-        flash: &HashSet<(usize, usize)>,
+        hud: &Hud,
+        effects: &MoveEffects,
     ) -> io::Result<()> {
         self.0
             .draw(|f| -> () {
                 let board = game.board();
                 let area = f.area();
-                let board_size = board.len() as u16;
-                let board_w = CELL_W * board_size;
-                let board_h = CELL_H * board_size;
+                let size = game.size();
+                let board_w = CELL_W * size.width as u16;
+                let board_h = CELL_H * size.height as u16;
 
                 let chunks = Layout::default()
                     .direction(Direction::Vertical)
@@ -241,7 +321,7 @@ impl TermGuard {
                     Rect::new(header_area.x, header_area.y, header_area.width, 1),
                 );
 
-                let score_line = Paragraph::new(Line::from(vec![
+                let mut score_spans = vec![
                     Span::styled("SCORE  ", Style::default().fg(Color::Rgb(100, 100, 100))),
                     Span::styled(
                         game.score().to_string(),
@@ -249,8 +329,57 @@ impl TermGuard {
                             .fg(Color::Rgb(230, 230, 230))
                             .add_modifier(Modifier::BOLD),
                     ),
-                ]))
-                .alignment(Alignment::Center);
+                    Span::styled("    BEST  ", Style::default().fg(Color::Rgb(100, 100, 100))),
+                    Span::styled(
+                        hud.best.unwrap_or(0).max(game.score()).to_string(),
+                        Style::default()
+                            .fg(Color::Rgb(230, 230, 230))
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                ];
+                if let Some(left) = game.moves_left() {
+                    score_spans.push(Span::styled(
+                        "    MOVES LEFT  ",
+                        Style::default().fg(Color::Rgb(100, 100, 100)),
+                    ));
+                    score_spans.push(Span::styled(
+                        left.to_string(),
+                        Style::default()
+                            .fg(if left < 10 {
+                                Color::Rgb(255, 80, 80)
+                            } else {
+                                Color::Rgb(230, 230, 230)
+                            })
+                            .add_modifier(Modifier::BOLD),
+                    ));
+                }
+                if let Some(clock) = hud.clock {
+                    score_spans.push(Span::styled(
+                        "    TIME  ",
+                        Style::default().fg(Color::Rgb(100, 100, 100)),
+                    ));
+                    score_spans.push(Span::styled(
+                        countdown::format(clock),
+                        Style::default()
+                            .fg(if clock < Duration::from_secs(10) {
+                                Color::Rgb(255, 80, 80)
+                            } else {
+                                Color::Rgb(230, 230, 230)
+                            })
+                            .add_modifier(Modifier::BOLD),
+                    ));
+                }
+                if let Some(banner) = hud.banner {
+                    score_spans.push(Span::raw("    "));
+                    score_spans.push(Span::styled(
+                        banner,
+                        Style::default()
+                            .fg(Color::Rgb(255, 215, 0))
+                            .add_modifier(Modifier::BOLD),
+                    ));
+                }
+                let score_line =
+                    Paragraph::new(Line::from(score_spans)).alignment(Alignment::Center);
                 f.render_widget(
                     score_line,
                     Rect::new(header_area.x, header_area.y + 1, header_area.width, 1),
@@ -261,59 +390,211 @@ impl TermGuard {
                 let y_base =
                     board_area.y as i16 + board_area.height.saturating_sub(board_h) as i16 / 2;
 
-                for (row_i, row) in board.iter().enumerate() {
-                    for (col_i, &val) in row.iter().enumerate() {
-                        let cx = x_base + col_i as i16 * CELL_W as i16 + x_shift;
-                        let cy = y_base + row_i as i16 * CELL_H as i16 + y_shift;
-                        if cx < 0 || cy < 0 {
-                            continue;
+                let cell_at = |(row, col): (f32, f32)| {
+                    (
+                        x_base + (col * CELL_W as f32).round() as i16,
+                        y_base + (row * CELL_H as f32).round() as i16,
+                    )
+                };
+                let cell = |(row, col): (usize, usize)| cell_at((row as f32, col as f32));
+
+                for row in 0..size.height {
+                    for col in 0..size.width {
+                        draw_tile(f, cell((row, col)), (CELL_W, CELL_H), Tile::EMPTY, EMPTY_BG);
+                    }
+                }
+
+                match (&effects.outcome, effects.phase()) {
+                    (Some(outcome), Some(Phase::Slide(t))) => {
+                        for tile_move in &outcome.tiles {
+                            let lerp = |from: usize, to: usize| {
+                                from as f32 + (to as f32 - from as f32) * t
+                            };
+                            let at = cell_at((
+                                lerp(tile_move.from.0, tile_move.to.0),
+                                lerp(tile_move.from.1, tile_move.to.1),
+                            ));
+                            draw_tile(
+                                f,
+                                at,
+                                (CELL_W, CELL_H),
+                                tile_move.tile,
+                                tile_color(tile_move.tile),
+                            );
                         }
-                        let cx = cx as u16;
-                        let cy = cy as u16;
-                        if cx + CELL_W > area.width || cy + CELL_H > area.height {
-                            continue;
+                    }
+                    (Some(outcome), Some(Phase::Pop(t))) => {
+                        for (row_i, row) in board.iter().enumerate() {
+                            for (col_i, &tile) in row.iter().enumerate() {
+                                let spawned = outcome
+                                    .spawns
+                                    .iter()
+                                    .any(|spawn| spawn.at == (row_i, col_i));
+                                if tile == Tile::EMPTY || spawned {
+                                    continue;
+                                }
+                                draw_tile(
+                                    f,
+                                    cell((row_i, col_i)),
+                                    (CELL_W, CELL_H),
+                                    tile,
+                                    tile_color(tile),
+                                );
+                            }
                         }
+                        // Merged tiles swell by a column on each side, then settle back
+                        for merge in &outcome.merges {
+                            let (x, y) = cell(merge.at);
+                            let bg = brighten(tile_color(merge.tile), (70.0 * (1.0 - t)) as u8);
+                            if t < 0.5 {
+                                draw_tile(f, (x - 1, y), (CELL_W + 2, CELL_H), merge.tile, bg);
+                            } else {
+                                draw_tile(f, (x, y), (CELL_W, CELL_H), merge.tile, bg);
+                            }
+                        }
+                        // Spawned tiles grow from their label to a full cell, fading in
+                        for spawn in &outcome.spawns {
+                            let (x, y) = cell(spawn.at);
+                            let w = 4 + ((CELL_W - 4) as f32 * t / 2.0).round() as u16 * 2;
+                            let h = 3 + ((CELL_H - 3) as f32 * t / 2.0).round() as u16 * 2;
+                            draw_tile(
+                                f,
+                                (x + ((CELL_W - w) / 2) as i16, y + ((CELL_H - h) / 2) as i16),
+                                (w, h),
+                                spawn.tile,
+                                blend(EMPTY_BG, tile_color(spawn.tile), t),
+                            );
+                        }
+                    }
+                    _ => {
+                        for (row_i, row) in board.iter().enumerate() {
+                            for (col_i, &tile) in row.iter().enumerate() {
+                                if tile != Tile::EMPTY {
+                                    draw_tile(
+                                        f,
+                                        cell((row_i, col_i)),
+                                        (CELL_W, CELL_H),
+                                        tile,
+                                        tile_color(tile),
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
 
-                        let cell_area = Rect::new(cx, cy, CELL_W, CELL_H);
-                        let is_flash = flash.contains(&(row_i, col_i)) && val != Tile::Empty;
-
-                        let (fg, bg) = if val == Tile::Empty {
-                            (EMPTY_BG, EMPTY_BG)
-                        } else {
-                            let base = tile_color(val);
-                            let bg = if is_flash { brighten(base, 70) } else { base };
-                            (Color::White, bg)
-                        };
+                if let Some(placement) = hud.placement {
+                    let (x, y) = cell(placement.at);
+                    let free = board[placement.at.0][placement.at.1] == Tile::EMPTY;
+                    if free {
+                        draw_tile(
+                            f,
+                            (x, y),
+                            (CELL_W, CELL_H),
+                            placement.tile,
+                            blend(EMPTY_BG, tile_color(placement.tile), 0.4),
+                        );
+                    }
+                    if x >= 0
+                        && y >= 0
+                        && x as u16 + CELL_W <= area.width
+                        && y as u16 + CELL_H <= area.height
+                    {
+                        f.render_widget(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .border_type(BorderType::Thick)
+                                .border_style(Style::default().fg(if free {
+                                    Color::Rgb(255, 215, 0)
+                                } else {
+                                    Color::Rgb(255, 80, 80)
+                                })),
+                            Rect::new(x as u16, y as u16, CELL_W, CELL_H),
+                        );
+                    }
+                }
 
-                        let block = Block::default()
-                            .borders(Borders::ALL)
-                            .border_type(BorderType::Rounded)
-                            .style(Style::default().bg(bg));
-
-                        let inner = block.inner(cell_area);
-                        f.render_widget(block, cell_area);
-
-                        if val != Tile::Empty {
-                            let text = Paragraph::new(Span::styled(
-                                val.to_string(),
-                                Style::default().fg(fg).bg(bg).add_modifier(Modifier::BOLD),
-                            ))
-                            .alignment(Alignment::Center);
-                            let mid_y = inner.y + inner.height / 2;
-                            f.render_widget(text, Rect::new(inner.x, mid_y, inner.width, 1));
-                        }
+                if let Some(hint) = hud.hint {
+                    let (ax, ay) = match hint {
+                        Move::Left => (x_base - 2, y_base + board_h as i16 / 2),
+                        Move::Right => (x_base + board_w as i16 + 1, y_base + board_h as i16 / 2),
+                        Move::Up => (x_base + board_w as i16 / 2, y_base - 1),
+                        Move::Down => (x_base + board_w as i16 / 2, y_base + board_h as i16),
+                        Move::Dont => (-1, -1),
+                    };
+                    if ax >= 0 && ay >= 0 && (ax as u16) < area.width && (ay as u16) < area.height {
+                        f.render_widget(
+                            Paragraph::new(Span::styled(
+                                arrow(hint),
+                                Style::default()
+                                    .fg(Color::Rgb(20, 220, 120))
+                                    .add_modifier(Modifier::BOLD),
+                            )),
+                            Rect::new(ax as u16, ay as u16, 1, 1),
+                        );
                     }
                 }
 
-                let footer_line = if let Some(msg) = message {
+                let footer_line = if let Some(msg) = hud.message {
                     Line::from(Span::styled(
                         msg,
                         Style::default()
                             .fg(Color::Rgb(255, 80, 80))
                             .add_modifier(Modifier::BOLD),
                     ))
-                } else {
+                } else if let Some(hint) = hud.hint {
+                    Line::from(vec![
+                        Span::raw("Hint: "),
+                        Span::styled(
+                            format!("{} {hint:?}", arrow(hint)),
+                            Style::default()
+                                .fg(Color::Rgb(20, 220, 120))
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                    ])
+                } else if let Some(placement) = hud.placement {
+                    let key = Style::default()
+                        .fg(Color::Rgb(200, 200, 200))
+                        .add_modifier(Modifier::BOLD);
+                    Line::from(vec![
+                        Span::styled("WASD", key),
+                        Span::raw(" / arrows: pick a cell  ·  "),
+                        Span::styled("Space", key),
+                        Span::raw(": change tile  ·  "),
+                        Span::styled("Enter", key),
+                        Span::raw(format!(": place the {}  ·  ", placement.tile)),
+                        Span::styled("U", key),
+                        Span::raw(": take back the move  ·  "),
+                        Span::styled("Q", key),
+                        Span::raw(": quit"),
+                    ])
+                } else if let Some(autoplay) = hud.autoplay {
+                    let key = Style::default()
+                        .fg(Color::Rgb(200, 200, 200))
+                        .add_modifier(Modifier::BOLD);
                     Line::from(vec![
+                        Span::styled(
+                            if autoplay.is_paused() {
+                                "AUTOPLAY PAUSED"
+                            } else {
+                                "AUTOPLAY"
+                            },
+                            Style::default()
+                                .fg(Color::Rgb(20, 220, 120))
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(format!(" {}ms  ·  ", autoplay.speed().as_millis())),
+                        Span::styled("Space", key),
+                        Span::raw(": pause  ·  "),
+                        Span::styled("+ / -", key),
+                        Span::raw(": speed  ·  "),
+                        Span::styled("WASD", key),
+                        Span::raw(" / arrows: take over  ·  "),
+                        Span::styled("Q", key),
+                        Span::raw(": quit"),
+                    ])
+                } else {
+                    let mut spans = vec![
                         Span::styled(
                             "WASD",
                             Style::default()
@@ -322,19 +603,66 @@ impl TermGuard {
                         ),
                         Span::raw(" / arrows: move  ·  "),
                         Span::styled(
-                            "Q",
+                            "U",
                             Style::default()
                                 .fg(Color::Rgb(200, 200, 200))
                                 .add_modifier(Modifier::BOLD),
                         ),
-                        Span::raw(": quit  ·  Win: "),
+                        Span::raw(match game.undos_left() {
+                            Some(left) => format!(": undo ({left} left) / "),
+                            None => ": undo / ".to_string(),
+                        }),
                         Span::styled(
-                            game.winning().to_string(),
+                            "R",
                             Style::default()
-                                .fg(tile_color(game.winning()))
+                                .fg(Color::Rgb(200, 200, 200))
                                 .add_modifier(Modifier::BOLD),
                         ),
-                    ])
+                        Span::raw(": redo  ·  "),
+                        Span::styled(
+                            "H",
+                            Style::default()
+                                .fg(Color::Rgb(200, 200, 200))
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(": hint  ·  "),
+                        Span::styled(
+                            "Q",
+                            Style::default()
+                                .fg(Color::Rgb(200, 200, 200))
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        Span::raw(": quit  ·  "),
+                    ];
+                    match game.goal() {
+                        Some(goal) => {
+                            spans.push(Span::raw("Goal: "));
+                            spans.push(Span::styled(
+                                goal.to_string(),
+                                Style::default()
+                                    .fg(Color::Rgb(255, 215, 0))
+                                    .add_modifier(Modifier::BOLD),
+                            ));
+                        }
+                        None => {
+                            spans.push(Span::raw("Win: "));
+                            spans.push(Span::styled(
+                                game.winning().to_string(),
+                                Style::default()
+                                    .fg(tile_color(game.winning()))
+                                    .add_modifier(Modifier::BOLD),
+                            ));
+                        }
+                    }
+                    // Puzzles spawn the same tiles whatever the seed
+                    if let (Some(seed), None) = (game.seed(), game.goal()) {
+                        spans.push(Span::raw("  ·  Seed: "));
+                        spans.push(Span::styled(
+                            seed.to_string(),
+                            Style::default().fg(Color::Rgb(200, 200, 200)),
+                        ));
+                    }
+                    Line::from(spans)
                 };
                 f.render_widget(
                     Paragraph::new(footer_line).alignment(Alignment::Center),
@@ -344,6 +672,112 @@ impl TermGuard {
             .map_err(|e| io::Error::other(e.to_string()))?;
         Ok(())
     }
+
+    /// Draws a list to pick from, each item is a name and a short summary,
+    /// the selected item also shows its description underneath
+    pub fn render_menu(
+        &mut self,
+        title: &str,
+        items: &[(&str, String)],
+        selected: usize,
+        description: &str,
+    ) -> io::Result<()> {
+        self.0
+            .draw(|f| {
+                let area = f.area();
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([
+                        Constraint::Length(HEADER_H),
+                        Constraint::Min(items.len() as u16 + 2),
+                        Constraint::Length(FOOTER_H),
+                    ])
+                    .split(area);
+
+                f.render_widget(
+                    Paragraph::new(Span::styled(
+                        title,
+                        Style::default()
+                            .fg(Color::Rgb(255, 215, 0))
+                            .add_modifier(Modifier::BOLD),
+                    ))
+                    .alignment(Alignment::Center),
+                    chunks[0],
+                );
+
+                let lines: Vec<Line> = items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (name, summary))| {
+                        let (marker, style) = if i == selected {
+                            (
+                                "▶",
+                                Style::default()
+                                    .fg(Color::Rgb(255, 215, 0))
+                                    .add_modifier(Modifier::BOLD),
+                            )
+                        } else {
+                            (" ", Style::default().fg(Color::Rgb(200, 200, 200)))
+                        };
+                        Line::from(vec![
+                            Span::styled(format!("{marker} {:>2}. {name:<20}", i + 1), style),
+                            Span::styled(
+                                summary.clone(),
+                                Style::default().fg(Color::Rgb(100, 100, 100)),
+                            ),
+                        ])
+                    })
+                    .collect();
+                // The list is left aligned in a column centered on the screen
+                let list_w = lines
+                    .iter()
+                    .map(Line::width)
+                    .max()
+                    .unwrap_or(0)
+                    .min(area.width as usize) as u16;
+                let list_area = chunks[1];
+                f.render_widget(
+                    Paragraph::new(lines),
+                    Rect::new(
+                        list_area.x + (list_area.width - list_w) / 2,
+                        list_area.y,
+                        list_w,
+                        list_area.height,
+                    ),
+                );
+                f.render_widget(
+                    Paragraph::new(Span::styled(
+                        description,
+                        Style::default().fg(Color::Rgb(230, 230, 230)),
+                    ))
+                    .alignment(Alignment::Center),
+                    Rect::new(
+                        list_area.x,
+                        list_area.y + (items.len() as u16 + 1).min(list_area.height),
+                        list_area.width,
+                        1.min(list_area.height.saturating_sub(items.len() as u16 + 1)),
+                    ),
+                );
+
+                let key = Style::default()
+                    .fg(Color::Rgb(200, 200, 200))
+                    .add_modifier(Modifier::BOLD);
+                f.render_widget(
+                    Paragraph::new(Line::from(vec![
+                        Span::styled("W / S", key),
+                        Span::raw(" / arrows: pick  ·  "),
+                        Span::styled("Enter", key),
+                        Span::raw(": play  ·  "),
+                        Span::styled("Q", key),
+                        Span::raw(": quit"),
+                    ]))
+                    .alignment(Alignment::Center),
+                    chunks[2],
+                );
+            })
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        Ok(())
+    }
 }
 
 impl Drop for TermGuard {
@@ -353,3 +787,41 @@ impl Drop for TermGuard {
         let _ = self.0.show_cursor();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn animation_phases() {
+        assert_eq!(Phase::at(Duration::ZERO), Some(Phase::Slide(0.0)));
+        assert_eq!(Phase::at(SLIDE_DURATION), Some(Phase::Pop(0.0)));
+        assert!(matches!(
+            Phase::at(SLIDE_DURATION + POP_DURATION / 2),
+            Some(Phase::Pop(t)) if (t - 0.5).abs() < 1e-3
+        ));
+        assert_eq!(Phase::at(SLIDE_DURATION + POP_DURATION), None);
+    }
+
+    #[test]
+    fn disabled_effects_never_animate() {
+        let mut game = Game::with_seed(4, Tile::TWO_THOUSAND_FORTY_EIGHT, 1);
+        let outcome = Move::ALL
+            .into_iter()
+            .find_map(|mov| game.play(mov))
+            .unwrap();
+        let mut effects = MoveEffects::new(false);
+        effects.record_move(outcome);
+        assert!(effects.next_frame().is_none());
+    }
+
+    #[test]
+    fn labels_fit_in_cells() {
+        let tile = |exponent| Tile::from_exponent(exponent).unwrap();
+        assert_eq!(tile_label(tile(11), 8), "2048");
+        assert_eq!(tile_label(tile(17), 8), "131072");
+        assert_eq!(tile_label(tile(17), 4), "131k");
+        assert_eq!(tile_label(tile(30), 8), "1073741k");
+        assert_eq!(tile_label(tile(30), 4), "1G");
+    }
+}